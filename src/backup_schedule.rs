@@ -0,0 +1,348 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs::{read_to_string, write},
+    io::ErrorKind,
+    path::PathBuf,
+    str::FromStr,
+    time::Duration,
+};
+
+use chrono::{
+    DateTime, Local,
+    format::{Item, StrftimeItems},
+};
+use cron::Schedule;
+use poise::serenity_prelude::{Colour, Context, CreateEmbed, CreateMessage, prelude::TypeMapKey};
+use serde::{Deserialize, Serialize};
+use tokio::time::sleep;
+
+use crate::taurus::{report_staff, taurus_client};
+
+/// How often the scheduler checks for due backups
+const TICK: Duration = Duration::from_secs(30);
+
+/// A backup taken on a cron schedule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupSchedule {
+    pub name: String,
+    pub cron: String,
+    /// Name of each backup, with chrono `strftime` fields such as `%Y-%m-%d`
+    pub template: String,
+    /// Backups from this schedule to keep, 0 keeps them all
+    pub keep: usize,
+    /// Backups this schedule created, oldest first
+    #[serde(default)]
+    pub created: Vec<String>,
+}
+
+/// Converts a standard day of week field, where Sunday is 0 or 7, to the one
+/// of the `cron` crate, where Sunday is 1. Day names are left as they are.
+fn convert_weekdays(field: &str) -> Result<String, String> {
+    if field == "*" || field == "?" || field.contains(|c: char| c.is_ascii_alphabetic()) {
+        return Ok(field.to_string());
+    }
+    let invalid = || format!("Invalid day of week `{}`", field);
+    let parse_day = |day: &str| {
+        day.parse::<usize>()
+            .ok()
+            .filter(|day| *day <= 7)
+            .ok_or_else(invalid)
+    };
+    let mut days = BTreeSet::new();
+    for item in field.split(',') {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => {
+                let step = step.parse().ok().filter(|step| *step > 0);
+                (range, Some(step.ok_or_else(invalid)?))
+            }
+            None => (item, None),
+        };
+        let (first, last) = match range.split_once('-') {
+            Some((first, last)) => (parse_day(first)?, parse_day(last)?),
+            None if range == "*" => (0, 6),
+            // `3/2` steps from the day to the end of the week
+            None if step.is_some() => (parse_day(range)?, 6),
+            None => (parse_day(range)?, parse_day(range)?),
+        };
+        if first > last {
+            return Err(invalid());
+        }
+        for day in (first..=last).step_by(step.unwrap_or(1)) {
+            days.insert(day % 7 + 1);
+        }
+    }
+    Ok(days
+        .iter()
+        .map(usize::to_string)
+        .collect::<Vec<_>>()
+        .join(","))
+}
+
+/// Parses a cron expression. The usual five fields follow standard cron, six
+/// or seven fields with seconds and years are read as the `cron` crate does,
+/// with Sunday as day 1.
+pub fn parse_cron(expr: &str) -> Result<Schedule, String> {
+    let fields = expr.split_whitespace().collect::<Vec<_>>();
+    let expr = match fields.as_slice() {
+        [minute, hour, day, month, weekday] => format!(
+            "0 {} {} {} {} {}",
+            minute,
+            hour,
+            day,
+            month,
+            convert_weekdays(weekday)?
+        ),
+        _ => expr.to_string(),
+    };
+    Schedule::from_str(&expr).map_err(|e| format!("Invalid cron expression: {}", e))
+}
+
+/// Fills the date fields of a backup name template
+pub fn render_name(template: &str, at: &DateTime<Local>) -> Result<String, String> {
+    let items = StrftimeItems::new(template).collect::<Vec<_>>();
+    if items.iter().any(|item| matches!(item, Item::Error)) {
+        return Err(format!("Invalid date template `{}`", template));
+    }
+    let name = at.format_with_items(items.into_iter()).to_string();
+    if name.is_empty() || name.contains(char::is_whitespace) {
+        return Err(format!("`{}` is not a valid backup name", name));
+    }
+    Ok(name)
+}
+
+/// Backup schedules, persisted as JSON
+pub struct ScheduleStore {
+    schedules: Vec<BackupSchedule>,
+    path: PathBuf,
+}
+
+impl ScheduleStore {
+    /// Loads the schedules from `path`, starting empty if the file does not exist
+    pub fn load(path: PathBuf) -> Result<Self, String> {
+        let schedules = match read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json)
+                .map_err(|e| format!("Failed to parse schedules file: {}", e))?,
+            Err(e) if e.kind() == ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(format!("Failed to read schedules file: {}", e)),
+        };
+        Ok(Self { schedules, path })
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(&self.schedules)
+            .map_err(|e| format!("Failed to serialise schedules: {}", e))?;
+        write(&self.path, json).map_err(|e| format!("Failed to write schedules file: {}", e))
+    }
+
+    pub fn schedules(&self) -> &[BackupSchedule] {
+        &self.schedules
+    }
+
+    pub fn add(&mut self, schedule: BackupSchedule) -> Result<(), String> {
+        if self.schedules.iter().any(|s| s.name == schedule.name) {
+            return Err(format!(
+                "A schedule named `{}` already exists",
+                schedule.name
+            ));
+        }
+        self.schedules.push(schedule);
+        self.save()
+    }
+
+    pub fn remove(&mut self, name: &str) -> Result<Option<BackupSchedule>, String> {
+        let Some(index) = self.schedules.iter().position(|s| s.name == name) else {
+            return Ok(None);
+        };
+        let removed = self.schedules.remove(index);
+        self.save()?;
+        Ok(Some(removed))
+    }
+
+    /// Replaces the list of backups a schedule has created
+    fn set_created(&mut self, name: &str, created: Vec<String>) -> Result<(), String> {
+        if let Some(schedule) = self.schedules.iter_mut().find(|s| s.name == name) {
+            schedule.created = created;
+        }
+        self.save()
+    }
+}
+
+pub struct Schedules;
+
+impl TypeMapKey for Schedules {
+    type Value = ScheduleStore;
+}
+
+/// Whether a `BACKUP` reply reports that the backup was not created
+fn backup_failed(response: &str) -> bool {
+    let response = response.to_lowercase();
+    response.trim().is_empty()
+        || ["error", "fail", "invalid", "unknown", "denied"]
+            .iter()
+            .any(|word| response.contains(word))
+}
+
+/// Takes one scheduled backup and removes the ones past the retention limit
+async fn run_schedule(ctx: &Context, schedule: &BackupSchedule, at: &DateTime<Local>) {
+    let taurus = taurus_client(ctx).await;
+    let embed = CreateEmbed::new().title(format!("Scheduled backup: {}", schedule.name));
+    let backup = match render_name(&schedule.template, at) {
+        Ok(backup) => backup,
+        Err(e) => {
+            let embed = embed.colour(Colour::RED).description(e);
+            report_staff(ctx, CreateMessage::new().embed(embed)).await;
+            return;
+        }
+    };
+    let response = match taurus.backup(&backup).await {
+        Ok(response) => response,
+        Err(e) => {
+            println!("ERROR: Scheduled backup {} failed: {}", backup, e);
+            let embed = embed
+                .colour(Colour::RED)
+                .description(format!("Failed to create `{}`: {}", backup, e));
+            report_staff(ctx, CreateMessage::new().embed(embed)).await;
+            return;
+        }
+    };
+    if backup_failed(&response) {
+        println!("ERROR: Scheduled backup {} failed: {}", backup, response);
+        let embed = embed
+            .colour(Colour::RED)
+            .description(format!("Taurus did not create `{}`", backup))
+            .field("Response", response, false);
+        report_staff(ctx, CreateMessage::new().embed(embed)).await;
+        return;
+    }
+    println!("INFO: Created scheduled backup {}", backup);
+
+    // A name rendered again replaced the earlier backup, which makes the new
+    // one the newest rather than a second entry the retention could remove
+    let mut created = schedule.created.clone();
+    created.retain(|name| *name != backup);
+    created.push(backup.clone());
+    let mut removed = Vec::new();
+    let mut failures = Vec::new();
+    while schedule.keep > 0 && created.len() > schedule.keep {
+        let oldest = created[0].clone();
+        match taurus.rm_backup(&oldest).await {
+            Ok(_) => {
+                created.remove(0);
+                removed.push(format!("`{}`", oldest));
+            }
+            Err(e) => {
+                failures.push(format!("`{}`: {}", oldest, e));
+                break;
+            }
+        }
+    }
+    {
+        let mut data = ctx.data.write().await;
+        let store = data.get_mut::<Schedules>().expect("Schedules not found");
+        if let Err(e) = store.set_created(&schedule.name, created) {
+            println!("ERROR: {}", e);
+        }
+    }
+
+    let mut embed = embed
+        .colour(if failures.is_empty() {
+            Colour::DARK_GREEN
+        } else {
+            Colour::ORANGE
+        })
+        .field("Created", format!("`{}`", backup), false)
+        .field("Response", response, false);
+    if !removed.is_empty() {
+        embed = embed.field("Removed", removed.join(", "), false);
+    }
+    if !failures.is_empty() {
+        embed = embed.field("Failed to remove", failures.join("\n"), false);
+    }
+    report_staff(ctx, CreateMessage::new().embed(embed)).await;
+}
+
+/// Takes backups whenever a schedule is due
+pub async fn run_backup_scheduler(ctx: &Context) {
+    // The next run of each schedule, along with the expression it came from
+    let mut next_runs: HashMap<String, (String, Option<DateTime<Local>>)> = HashMap::new();
+    loop {
+        let now = Local::now();
+        let schedules = {
+            let data = ctx.data.read().await;
+            let store = data.get::<Schedules>().expect("Schedules not found");
+            store.schedules().to_vec()
+        };
+        next_runs.retain(|name, _| schedules.iter().any(|s| &s.name == name));
+        for schedule in &schedules {
+            let cron = match parse_cron(&schedule.cron) {
+                Ok(cron) => cron,
+                Err(e) => {
+                    println!("ERROR: Schedule {}: {}", schedule.name, e);
+                    continue;
+                }
+            };
+            let entry = next_runs
+                .entry(schedule.name.clone())
+                .or_insert_with(|| (schedule.cron.clone(), cron.after(&now).next()));
+            if entry.0 != schedule.cron {
+                *entry = (schedule.cron.clone(), cron.after(&now).next());
+            }
+            if entry.1.is_some_and(|next| next <= now) {
+                entry.1 = cron.after(&now).next();
+                run_schedule(ctx, schedule, &now).await;
+            }
+        }
+        sleep(TICK).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Datelike, Utc, Weekday};
+
+    use super::*;
+
+    fn weekdays(expr: &str) -> Vec<Weekday> {
+        let mut days = parse_cron(expr)
+            .unwrap()
+            .upcoming(Utc)
+            .take(14)
+            .map(|at| at.weekday())
+            .collect::<Vec<_>>();
+        days.sort_by_key(Weekday::num_days_from_monday);
+        days.dedup();
+        days
+    }
+
+    #[test]
+    fn reads_standard_weekdays() {
+        use Weekday::*;
+        assert_eq!(weekdays("0 4 * * 1-5"), [Mon, Tue, Wed, Thu, Fri]);
+        assert_eq!(weekdays("0 4 * * 0"), [Sun]);
+        assert_eq!(weekdays("0 4 * * 7"), [Sun]);
+        assert_eq!(weekdays("0 4 * * 5-7"), [Fri, Sat, Sun]);
+        assert_eq!(weekdays("0 4 * * */3"), [Wed, Sat, Sun]);
+        assert_eq!(weekdays("0 4 * * SAT,SUN"), [Sat, Sun]);
+    }
+
+    #[test]
+    fn rejects_invalid_weekdays() {
+        assert!(parse_cron("0 4 * * 8").is_err());
+        assert!(parse_cron("0 4 * * 5-1").is_err());
+        assert!(parse_cron("0 4 * * 1/0").is_err());
+    }
+
+    #[test]
+    fn passes_longer_expressions_through() {
+        assert_eq!(weekdays("0 0 4 * * 1"), [Weekday::Sun]);
+    }
+
+    #[test]
+    fn spots_failed_backups() {
+        assert!(backup_failed(""));
+        assert!(backup_failed("Error: session SMP not found"));
+        assert!(backup_failed("Backup failed"));
+        assert!(!backup_failed("Created backup smp-2025-01-01"));
+    }
+}
@@ -0,0 +1,67 @@
+use std::{collections::BTreeMap, sync::LazyLock};
+
+use fasteval::EvalNamespace;
+use regex::{Captures, Regex};
+
+/// Items in a stack, a shulker box and a double chest
+const STACK: f64 = 64.0;
+const SHULKER_BOX: f64 = 27.0 * STACK;
+const DOUBLE_CHEST: f64 = 54.0 * STACK;
+
+/// A number directly followed by a unit, e.g. `3sb` or `1.5dc`. The prefix
+/// keeps digits at the end of names such as `x3sb` from matching.
+static UNIT_SUFFIX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(^|[^A-Za-z0-9_.])(\d+(?:\.\d+)?|\.\d+)(st|sb|dc)\b")
+        .expect("Failed to compile unit suffix regex")
+});
+
+/// Rewrites `3sb + 12st` to `(3*sb) + (12*st)`, which fasteval can parse
+pub fn expand_units(expr: &str) -> String {
+    UNIT_SUFFIX
+        .replace_all(expr, |caps: &Captures| {
+            format!("{}({}*{})", &caps[1], &caps[2], &caps[3])
+        })
+        .into_owned()
+}
+
+/// The built-in constants and functions
+///
+/// * `st`, `sb`, `dc` - items in a stack, shulker box and double chest
+/// * `stacks(n)`, `sb(n)`, `dc(n)` - how many of those `n` items fill
+/// * `nether(x)`, `ow(x)` - a coordinate in the nether or the overworld
+/// * `rate(items, minutes)` - items per hour
+fn builtin(name: &str, args: &[f64]) -> Option<f64> {
+    match (name, args) {
+        ("st", []) => Some(STACK),
+        ("sb", []) => Some(SHULKER_BOX),
+        ("dc", []) => Some(DOUBLE_CHEST),
+        ("stacks", [n]) => Some(n / STACK),
+        ("sb", [n]) => Some(n / SHULKER_BOX),
+        ("dc", [n]) => Some(n / DOUBLE_CHEST),
+        ("nether", [x]) => Some(x / 8.0),
+        ("ow", [x]) => Some(x * 8.0),
+        ("rate", [items, minutes]) => Some(items / minutes * 60.0),
+        _ => None,
+    }
+}
+
+/// A player's variables with the built-ins behind them, so variables can
+/// shadow the constants
+pub struct Namespace<'a> {
+    scopes: &'a mut Vec<BTreeMap<String, f64>>,
+}
+
+impl<'a> Namespace<'a> {
+    pub fn new(scopes: &'a mut Vec<BTreeMap<String, f64>>) -> Self {
+        Self { scopes }
+    }
+}
+
+impl EvalNamespace for Namespace<'_> {
+    fn lookup(&mut self, name: &str, args: Vec<f64>, keybuf: &mut String) -> Option<f64> {
+        if let Some(value) = self.scopes.lookup(name, args.clone(), keybuf) {
+            return Some(value);
+        }
+        builtin(name, &args)
+    }
+}
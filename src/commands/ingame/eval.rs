@@ -1,18 +1,15 @@
 use std::collections::BTreeMap;
 
+use crate::{
+    EvalUser,
+    calculator::{Namespace, expand_units},
+    commands::prelude::Error,
+    taurus::TextComponent,
+};
 use fasteval::{Evaler, Parser, Slab};
 use poise::serenity_prelude::Context;
 
-use crate::{EvalUser, commands::prelude::Error};
-
-async fn send_msg(tx: &tokio::sync::mpsc::Sender<String>, message: &str) -> Result<(), Error> {
-    if message.trim().is_empty() {
-        return Ok(());
-    }
-    let cmd = format!("MSG {}", message);
-    tx.send(cmd).await.expect("Taurus dead");
-    Ok(())
-}
+use super::Invocation;
 
 async fn eval_internal(ctx: &Context, username: &str, eval_string: &str) -> Result<String, Error> {
     let parser = Parser::new();
@@ -20,7 +17,7 @@ async fn eval_internal(ctx: &Context, username: &str, eval_string: &str) -> Resu
     let mut data = ctx.data.write().await;
     let namespaces = data
         .get_mut::<crate::EvalRepl>()
-        .expect("TaurusChannel not found");
+        .expect("EvalRepl not found");
     let ns = namespaces
         .entry(username.to_owned())
         .or_insert_with(|| EvalUser {
@@ -54,16 +51,17 @@ async fn eval_internal(ctx: &Context, username: &str, eval_string: &str) -> Resu
 
         return Ok(format!("Exited scope[{}]", ns.ns.len()));
     }
+    let line = expand_units(&line);
     let expr_ref = match parser.parse(&line, &mut slab.ps) {
         Ok(expr_i) => slab.ps.get_expr(expr_i),
-        Err(err) => {
+        Err(_) => {
             return Ok("parse error".to_string());
         }
     };
 
-    let ans = match expr_ref.eval(&slab, &mut ns.ns) {
+    let ans = match expr_ref.eval(&slab, &mut Namespace::new(&mut ns.ns)) {
         Ok(val) => val,
-        Err(err) => {
+        Err(_) => {
             return Ok("evaluation error".to_string());
         }
     };
@@ -74,13 +72,14 @@ async fn eval_internal(ctx: &Context, username: &str, eval_string: &str) -> Resu
     Ok(format!("{}", ans))
 }
 
-pub async fn eval(ctx: &Context, username: &str, eval_string: &str) -> Result<(), Error> {
-    let result = eval_internal(ctx, username, &eval_string.replace("\\_", "_")).await?;
-    let message = format!("{}", result);
-    let data = ctx.data.read().await;
-    let (tx, _rx) = data
-        .get::<crate::TaurusChannel>()
-        .expect("TaurusChannel not found");
-    send_msg(tx, &message).await?;
-    Ok(())
+pub async fn eval(
+    ctx: &Context,
+    invocation: &Invocation<'_>,
+    eval_string: &str,
+) -> Result<(), Error> {
+    let message = eval_internal(ctx, invocation.username, &eval_string.replace("\\_", "_")).await?;
+    if message.trim().is_empty() {
+        return Ok(());
+    }
+    invocation.tellraw(ctx, &TextComponent::new(message)).await
 }
@@ -1,24 +1,125 @@
 mod score;
 mod eval;
+mod rules;
+
+pub use rules::Cooldowns;
 
 
 use poise::serenity_prelude::Context;
 
-use crate::commands::prelude::Error;
+use crate::{
+    Config,
+    commands::{prelude::Error, shared},
+    taurus::{
+        Colour, TextComponent,
+        protocol::{Outbound, is_player_name},
+        taurus_client,
+    },
+};
+
+/// The player and server an in-game command came from
+pub struct Invocation<'a> {
+    pub server: &'a str,
+    pub username: &'a str,
+    /// Whether the output goes to everyone on the server
+    pub broadcast: bool,
+}
+
+impl Invocation<'_> {
+    /// Sends a tellraw component to the invoking player, or to everyone on the
+    /// server if the command broadcasts
+    pub async fn tellraw(&self, ctx: &Context, text: &TextComponent) -> Result<(), Error> {
+        let target = if self.broadcast { "@a" } else { self.username };
+        self.send(ctx, target, text).await
+    }
+
+    /// Tells the invoking player something nobody else needs to see
+    pub async fn deny(&self, ctx: &Context, reason: &str) -> Result<(), Error> {
+        let text = TextComponent::new(reason).colour(Colour::Red);
+        self.send(ctx, self.username, &text).await
+    }
+
+    async fn send(&self, ctx: &Context, target: &str, text: &TextComponent) -> Result<(), Error> {
+        let taurus = taurus_client(ctx).await;
+        taurus
+            .send(Outbound::Rcon {
+                server: self.server.to_string(),
+                command: format!("tellraw {} {}", target, text),
+            })
+            .await?;
+        Ok(())
+    }
+}
+
+async fn run_shared(
+    ctx: &Context,
+    invocation: &Invocation<'_>,
+    command: &shared::SharedCommand,
+    args: &[&str],
+) -> Result<(), Error> {
+    if !command.allowed_ingame(ctx, invocation.username).await {
+        let reason = format!("Sorry, you are not allowed to use {}", command.name);
+        return invocation.deny(ctx, &reason).await;
+    }
+    if args.len() < command.args.len() {
+        let prefix = {
+            let data = ctx.data.read().await;
+            let config = data.get::<Config>().expect("Config not found");
+            config.prefix.first().cloned().unwrap_or_default()
+        };
+        let usage = format!("Usage: {}", command.usage(&prefix));
+        return invocation.deny(ctx, &usage).await;
+    }
+    let text = match command.run(ctx, args).await {
+        Ok(reply) => reply.to_tellraw(),
+        Err(e) => {
+            TextComponent::new(format!("{} failed: {}", command.name, e)).colour(Colour::Red)
+        }
+    };
+    invocation.tellraw(ctx, &text).await
+}
 
 pub async fn execute_ingame_command(ctx: &Context, server: &str, username: &str, command: &str, args: &[&str]) -> Result<(), Error> {
+    // The name ends up in a tellraw selector, so it must not smuggle in more
+    if !is_player_name(username) {
+        return Ok(());
+    }
+    let broadcast = {
+        let data = ctx.data.read().await;
+        let config = data.get::<Config>().expect("Config not found");
+        config
+            .ingame_commands
+            .get(command)
+            .is_some_and(|opts| opts.broadcast)
+    };
+    let invocation = Invocation {
+        server,
+        username,
+        broadcast,
+    };
+    let known = matches!(command, "score" | "eval") || shared::find(command).is_some();
+    if !known {
+        return Ok(());
+    }
+    if let Some(reason) = rules::check(ctx, &invocation, command).await {
+        return invocation.deny(ctx, &reason).await;
+    }
     match command {
         "score" => {
-            let Some(board) = args.get(0) else {
+            let Some(board) = args.first() else {
                 return Ok(())
             };
-            score::score(ctx, server, board).await;
+            score::score(ctx, &invocation, board).await?;
         }
         "eval" => {
             let eval_string = args.join(" ");
-            eval::eval(ctx, username, &eval_string).await?;
+            eval::eval(ctx, &invocation, &eval_string).await?;
+        }
+        _ => {
+            if let Some(command) = shared::find(command) {
+                run_shared(ctx, &invocation, command, args).await?;
+            }
         }
-        _ => {}
     }
     Ok(())
 }
@@ -0,0 +1,123 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use poise::serenity_prelude::{Context, GuildId, RoleId, prelude::TypeMapKey};
+
+use crate::{
+    config::{Config, IngameCommandOpts},
+    links::Links,
+    whitelist_sync::has_role,
+};
+
+use super::Invocation;
+
+/// Cooldowns older than this are forgotten
+const FORGET_AFTER: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// When each player last ran each command, keyed by command and player
+pub struct Cooldowns;
+
+impl TypeMapKey for Cooldowns {
+    type Value = HashMap<(String, String), Instant>;
+}
+
+/// The guild the role belongs to, from the cache
+fn role_guild(ctx: &Context, role: RoleId) -> Option<GuildId> {
+    ctx.cache.guilds().into_iter().find(|guild| {
+        ctx.cache
+            .guild(*guild)
+            .is_some_and(|guild| guild.roles.contains_key(&role))
+    })
+}
+
+/// Checks the configured rules of a command, returning why the player may not
+/// run it. Passing the checks starts the player's cooldown.
+pub async fn check(ctx: &Context, invocation: &Invocation<'_>, command: &str) -> Option<String> {
+    let (opts, user) = {
+        let data = ctx.data.read().await;
+        let config = data.get::<Config>().expect("Config not found");
+        let links = data.get::<Links>().expect("Links not found");
+        let opts = config.ingame_commands.get(command).cloned();
+        (opts, links.user(invocation.username))
+    };
+    // Commands without any rules are open to everyone
+    let IngameCommandOpts {
+        players,
+        role,
+        servers,
+        cooldown,
+        ..
+    } = opts?;
+
+    if let Some(servers) = servers
+        && !servers.iter().any(|server| server == invocation.server)
+    {
+        return Some(format!(
+            "Sorry, {} is not available on this server",
+            command
+        ));
+    }
+    if let Some(players) = players
+        && !players
+            .iter()
+            .any(|player| player.eq_ignore_ascii_case(invocation.username))
+    {
+        return Some(format!("Sorry, you are not allowed to use {}", command));
+    }
+    if let Some(role) = role {
+        let Some(user) = user else {
+            return Some(format!(
+                "Sorry, {} needs your account linked to Discord, use /link there first",
+                command
+            ));
+        };
+        let role = RoleId::new(role);
+        let allowed = match role_guild(ctx, role) {
+            Some(guild) => has_role(ctx, guild, user, role).await,
+            None => {
+                println!(
+                    "ERROR: Role {} required by {} is in no known guild",
+                    role, command
+                );
+                None
+            }
+        };
+        match allowed {
+            Some(true) => {}
+            Some(false) => {
+                return Some(format!(
+                    "Sorry, you need a Discord role you don't have to use {}",
+                    command
+                ));
+            }
+            None => {
+                return Some(format!(
+                    "Sorry, your Discord roles couldn't be checked, try {} again later",
+                    command
+                ));
+            }
+        }
+    }
+
+    if cooldown > 0 {
+        let mut data = ctx.data.write().await;
+        let cooldowns = data.get_mut::<Cooldowns>().expect("Cooldowns not found");
+        let key = (command.to_string(), invocation.username.to_lowercase());
+        let cooldown = Duration::from_secs(cooldown);
+        if let Some(last) = cooldowns.get(&key) {
+            let left = cooldown.saturating_sub(last.elapsed());
+            if !left.is_zero() {
+                return Some(format!(
+                    "Please wait {}s before using {} again",
+                    left.as_secs().max(1),
+                    command
+                ));
+            }
+        }
+        cooldowns.insert(key, Instant::now());
+        cooldowns.retain(|_, last| last.elapsed() < FORGET_AFTER);
+    }
+    None
+}
@@ -1,23 +1,19 @@
-use poise::{CreateReply, serenity_prelude::CreateEmbed};
+use poise::{
+    CreateReply,
+    serenity_prelude::{CreateEmbed, CreateEmbedFooter},
+};
 
-use crate::{commands::prelude::*, taurus::fetch_latest_with_type, TaurusChannel};
+use crate::{
+    commands::{prelude::*, public::paginate},
+    taurus::{protocol::BackupEntry, taurus_client},
+};
 
 use super::check_member;
 
-async fn send_and_recieve(ctx: Context<'_>, cmd: String, args: String) -> Result<String, Error> {
-    let cache = {
-        let data = ctx.serenity_context().data.read().await;
-        let (sender, cache) = data
-            .get::<TaurusChannel>()
-            .expect("TaurusChannel not found in context data");
-        sender.send(format!("{} {}", cmd, args)).await?;
-        cache.clone()
-    };
-    let response = fetch_latest_with_type(cache, &cmd).await?;
-    Ok(response)
-}
+/// Backups shown on each page of `/backup ls`
+const PAGE_SIZE: usize = 15;
 
-/// Gets the invite link for the Hypnos Discord server
+/// Manages the backups of the Taurus sessions
 ///
 /// # Arguments
 /// * `cmd` - The command to execute, which can be one of the following:
@@ -35,33 +31,92 @@ pub async fn backup(_: Context<'_>) -> Result<(), Error> {
     Ok(())
 }
 
-async fn gather_response(
-    ctx: Context<'_>,
-    cmd: String,
-    args: String,
-    title: &str,
-) -> Result<CreateReply, Error> {
-    let response = send_and_recieve(ctx.clone(), cmd, args).await?;
-
+fn gather_response(response: String, title: &str) -> CreateReply {
     let embed = CreateEmbed::default().title(title).description(response);
 
-    Ok(CreateReply::default().embed(embed))
+    CreateReply::default().embed(embed)
+}
+
+/// Lays the backups out as a fixed width table
+fn backup_table(entries: &[BackupEntry]) -> String {
+    let column = |value: &Option<String>| value.clone().unwrap_or_else(|| "-".to_string());
+    let rows = entries
+        .iter()
+        .map(|entry| (entry.name.clone(), column(&entry.date), column(&entry.size)))
+        .collect::<Vec<_>>();
+    let width = |header: &str, cell: fn(&(String, String, String)) -> &String| {
+        rows.iter()
+            .map(|row| cell(row).chars().count())
+            .chain([header.len()])
+            .max()
+            .unwrap_or_default()
+    };
+    let name_width = width("Name", |row| &row.0);
+    let date_width = width("Date", |row| &row.1);
+
+    let mut table = format!("{:name_width$}  {:date_width$}  Size\n", "Name", "Date");
+    for (name, date, size) in rows {
+        table.push_str(&format!(
+            "{:name_width$}  {:date_width$}  {}\n",
+            name, date, size
+        ));
+    }
+    format!("```{}```", table)
 }
 
 /// Lists the backups available
 #[command(slash_command, prefix_command, aliases("ls", "list"))]
 pub async fn ls(ctx: Context<'_>) -> Result<(), Error> {
-    let reply = gather_response(
-        ctx.clone(),
-        "LIST_BACKUPS".to_string(),
-        String::new(),
-        "Backup List",
-    )
-    .await?;
-    ctx.send(reply).await?;
+    let entries = taurus_client(ctx.serenity_context())
+        .await
+        .list_backups()
+        .await?;
+    if entries.is_empty() {
+        let reply = gather_response("No backups found".to_string(), "Backup List");
+        ctx.send(reply).await?;
+        return Ok(());
+    }
+
+    let page_count = entries.len().div_ceil(PAGE_SIZE);
+    let mut pages = Vec::new();
+    for (i, chunk) in entries.chunks(PAGE_SIZE).enumerate() {
+        let page = embed(&ctx)
+            .await?
+            .title(format!("Backup List ({} backups)", entries.len()))
+            .description(backup_table(chunk))
+            .footer(CreateEmbedFooter::new(format!(
+                "Page {}/{}",
+                i + 1,
+                page_count
+            )));
+        pages.push(page);
+    }
+    if pages.len() == 1 {
+        ctx.send(CreateReply::default().embed(pages.remove(0)))
+            .await?;
+    } else {
+        paginate(ctx, &pages).await?;
+    }
     Ok(())
 }
 
+async fn autocomplete_backup(ctx: Context<'_>, partial: &str) -> Vec<String> {
+    let Ok(entries) = taurus_client(ctx.serenity_context())
+        .await
+        .list_backups()
+        .await
+    else {
+        return Vec::new();
+    };
+    let partial = partial.to_lowercase();
+    entries
+        .into_iter()
+        .map(|entry| entry.name)
+        .filter(|name| name.to_lowercase().contains(&partial))
+        .take(25)
+        .collect()
+}
+
 /// Removes a backup by name
 ///
 /// # Arguments
@@ -69,16 +124,33 @@ pub async fn ls(ctx: Context<'_>) -> Result<(), Error> {
 #[command(slash_command, prefix_command, aliases("rm", "remove", "delete"))]
 async fn rm(
     ctx: Context<'_>,
-    #[description = "Name of the backup to remove"] backup_name: String,
+    #[description = "Name of the backup to remove"]
+    #[autocomplete = "autocomplete_backup"]
+    backup_name: String,
 ) -> Result<(), Error> {
-    let reply = gather_response(
-        ctx.clone(),
-        "RM_BACKUP".to_string(),
-        backup_name,
-        "Remove Backup",
-    )
-    .await?;
-    ctx.send(reply).await?;
+    let prompt = embed(&ctx)
+        .await?
+        .title("Remove Backup")
+        .description(format!("Remove the backup `{}`?", backup_name));
+    let (confirmed, handle) = confirm(ctx, prompt, "Remove").await?;
+    let description = match confirmed {
+        Some(true) => match taurus_client(ctx.serenity_context())
+            .await
+            .rm_backup(&backup_name)
+            .await
+        {
+            Ok(response) => response,
+            Err(e) => format!("Failed to remove `{}`: {}", backup_name, e),
+        },
+        Some(false) => format!("Kept `{}`", backup_name),
+        None => format!("Timed out, kept `{}`", backup_name),
+    };
+    let result = embed(&ctx)
+        .await?
+        .title("Remove Backup")
+        .description(description);
+    let reply = CreateReply::default().embed(result).components(Vec::new());
+    handle.edit(ctx, reply).await?;
     Ok(())
 }
 
@@ -91,13 +163,11 @@ async fn new(
     ctx: Context<'_>,
     #[description = "Name of the session to create a backup for"] backup_name: String,
 ) -> Result<(), Error> {
-    let reply = gather_response(
-        ctx.clone(),
-        "BACKUP".to_string(),
-        backup_name,
-        "Create Backup",
-    )
-    .await?;
+    let response = taurus_client(ctx.serenity_context())
+        .await
+        .backup(&backup_name)
+        .await?;
+    let reply = gather_response(response, "Create Backup");
     ctx.send(reply).await?;
     Ok(())
 }
@@ -3,11 +3,13 @@ mod grinder;
 mod session;
 mod reconnect;
 mod metadata;
+mod taurus;
 
 pub use backup::backup;
 pub use grinder::grinder;
 pub use session::session;
 pub use reconnect::reconnect;
+pub use taurus::taurus;
 
 use poise::serenity_prelude::RoleId;
 
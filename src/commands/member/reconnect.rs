@@ -1,22 +1,19 @@
-use poise::serenity_prelude::RoleId;
-
-use crate::{commands::prelude::*, taurus::TaurusChannel, Config};
+use crate::{commands::prelude::*, taurus::taurus_client};
 
 use super::check_member;
 
-/// Gets the invite link for the Hypnos Discord server
+/// Forces the bot to reconnect to Taurus
 #[command(slash_command, prefix_command, check = "check_member")]
 pub async fn reconnect(ctx: Context<'_>) -> Result<(), Error> {
-    {
-        let data = ctx.serenity_context().data.read().await;
-        let (sender, _cache) = data
-            .get::<TaurusChannel>()
-            .expect("TaurusChannel not found in context data");
-        sender.send("__RECONNECT__".to_owned()).await?;
-    }
+    let taurus = taurus_client(ctx.serenity_context()).await;
+    let state = taurus.state().await;
+    taurus.reconnect().await?;
     let embed = embed(&ctx).await?
         .title("Attempting to reconnect")
-        .description("Attempting to reconnect to taurus.");
+        .description(format!(
+            "Attempting to reconnect to taurus.\nPrevious state: {}",
+            state
+        ));
 
     let reply = CreateReply::default().embed(embed);
     ctx.send(reply).await?;
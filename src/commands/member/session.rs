@@ -1,46 +1,44 @@
-/// NC suboptimal protocol implementation for this one ngl
+use std::time::Duration;
 
-#[derive(Deserialize, Clone)]
-struct Rcon;
+use poise::serenity_prelude::CreateEmbed;
+use timeago::Formatter;
 
-#[derive(Deserialize, Clone)]
-struct Game {
-    chat_bridge: Option<bool>,
-    backup_interval: Option<u64>,
-    backup_keep: Option<u64>,
-}
-
-#[derive(Deserialize, Clone)]
-struct Session {
-    pub name: String,
-    pub description: Option<String>,
-    pub host: String,
-    pub game: Option<Game>,
-    pub rcon: Option<Rcon>,
-}
-
-use serde::Deserialize;
-
-use crate::taurus::fetch_latest_with_type;
-use crate::{TaurusChannel};
+use crate::{
+    commands::staff::check_staff,
+    taurus::{TaurusClient, taurus_client},
+};
 
 use crate::commands::prelude::*;
 
 use super::check_member;
 
-//// Lists the online players on the Hypnos server
-#[command(slash_command, prefix_command, check = "check_member")]
-pub async fn session(ctx: Context<'_>) -> Result<(), Error> {
-    let cache = {
-        let data = ctx.serenity_context().data.read().await;
-        let (sender, cache) = data
-            .get::<TaurusChannel>()
-            .expect("TaurusChannel not found in context data");
-        sender.send("LIST_SESSIONS".to_owned()).await?;
-        cache.clone()
-    };
-    let res = &fetch_latest_with_type(cache, "LIST_SESSIONS").await?[14..];
-    let sessions: Vec<Session> = serde_json::from_str(res).map_err(|e| "Bruh taurus fail")?;
+/// Controls the Taurus sessions
+///
+/// # Arguments
+/// * `cmd` - The command to execute, which can be one of the following:
+///     - `list`: List the sessions and their settings
+///     - `status`: Show whether a session is running
+///     - `start`: Start a session
+///     - `stop`: Stop a session
+///     - `restart`: Restart a session
+#[command(
+    slash_command,
+    prefix_command,
+    subcommands("list", "status", "start", "stop", "restart"),
+    subcommand_required,
+    check = "check_member"
+)]
+pub async fn session(_: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Lists the Taurus sessions and their settings
+#[command(slash_command, prefix_command)]
+async fn list(ctx: Context<'_>) -> Result<(), Error> {
+    let sessions = taurus_client(ctx.serenity_context())
+        .await
+        .list_sessions()
+        .await?;
     let mut embed = embed(&ctx).await?.title("Taurus Sessions");
     for s in sessions {
         embed = embed.field(format!("Name: {}", s.name), "", false);
@@ -49,7 +47,7 @@ pub async fn session(ctx: Context<'_>) -> Result<(), Error> {
         }
         embed = embed.field("Host", s.host, true);
 
-        if let Some(_) = s.rcon {
+        if s.rcon.is_some() {
             embed = embed.field("RCON", "Enabled", true);
         } else {
             embed = embed.field("RCON", "Disabled", true);
@@ -58,20 +56,19 @@ pub async fn session(ctx: Context<'_>) -> Result<(), Error> {
             if let Some(chat_bridge) = game.chat_bridge {
                 embed = embed.field("Chat Bridge", chat_bridge.to_string(), true);
             }
-            if let Some(backup_interval) = game.backup_interval {
-                if backup_interval > 0 {
-                    embed = embed.field(
-                        "Backup Interval",
-                        format!("{} seconds", backup_interval),
-                        true,
-                    );
-                }
+            if let Some(backup_interval) = game.backup_interval
+                && backup_interval > 0
+            {
+                embed = embed.field(
+                    "Backup Interval",
+                    format!("{} seconds", backup_interval),
+                    true,
+                );
             }
-            if let Some(backup_keep) = game.backup_keep {
-                if backup_keep > 0 {
-                    embed =
-                        embed.field("Backup Keep Time", format!("{} seconds", backup_keep), true);
-                }
+            if let Some(backup_keep) = game.backup_keep
+                && backup_keep > 0
+            {
+                embed = embed.field("Backup Keep Time", format!("{} seconds", backup_keep), true);
             }
         }
     }
@@ -79,3 +76,158 @@ pub async fn session(ctx: Context<'_>) -> Result<(), Error> {
     ctx.send(reply).await?;
     Ok(())
 }
+
+async fn autocomplete_session(ctx: Context<'_>, partial: &str) -> Vec<String> {
+    let Ok(sessions) = taurus_client(ctx.serenity_context())
+        .await
+        .list_sessions()
+        .await
+    else {
+        return Vec::new();
+    };
+    let partial = partial.to_lowercase();
+    sessions
+        .into_iter()
+        .map(|session| session.name)
+        .filter(|name| name.to_lowercase().contains(&partial))
+        .take(25)
+        .collect()
+}
+
+/// Adds the live state of a session to an embed
+async fn status_fields(
+    taurus: &TaurusClient,
+    name: &str,
+    embed: CreateEmbed,
+) -> Result<CreateEmbed, Error> {
+    let status = taurus.session_status(name).await?;
+    let state = if status.running { "Running" } else { "Stopped" };
+    let players = match &status.players {
+        Some(players) if players.is_empty() => "0".to_string(),
+        Some(players) => format!("{} ({})", players.len(), players.join(", ")),
+        None => "Unknown".to_string(),
+    };
+    let uptime = match status.uptime {
+        Some(uptime) if status.running => {
+            let mut f = Formatter::new();
+            f.num_items(3);
+            f.ago("");
+            f.convert(Duration::from_secs(uptime))
+        }
+        _ => "Not running".to_string(),
+    };
+    Ok(embed
+        .field("State", state, true)
+        .field("Players", players, true)
+        .field("Uptime", uptime, true))
+}
+
+/// Shows whether a session is running, who is on it and for how long
+#[command(slash_command, prefix_command)]
+async fn status(
+    ctx: Context<'_>,
+    #[description = "Name of the session"]
+    #[autocomplete = "autocomplete_session"]
+    name: String,
+) -> Result<(), Error> {
+    let embed = embed(&ctx).await?.title(format!("Session {}", name));
+    let embed = status_fields(&taurus_client(ctx.serenity_context()).await, &name, embed).await?;
+    ctx.send(CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+/// Starts a session
+#[command(slash_command, prefix_command)]
+async fn start(
+    ctx: Context<'_>,
+    #[description = "Name of the session"]
+    #[autocomplete = "autocomplete_session"]
+    name: String,
+) -> Result<(), Error> {
+    ctx.defer().await?;
+    let taurus = taurus_client(ctx.serenity_context()).await;
+    let response = taurus.start_session(&name).await?;
+    let embed = embed(&ctx)
+        .await?
+        .title(format!("Started {}", name))
+        .description(response);
+    // The session started even if its status can't be fetched
+    let embed = status_fields(&taurus, &name, embed.clone())
+        .await
+        .unwrap_or(embed);
+    ctx.send(CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+/// Stops or restarts a session once the author confirms
+async fn destructive(ctx: Context<'_>, name: String, restart: bool) -> Result<(), Error> {
+    let (verb, done) = if restart {
+        ("Restart", "Restarted")
+    } else {
+        ("Stop", "Stopped")
+    };
+    let prompt = embed(&ctx)
+        .await?
+        .title(format!("{} {}", verb, name))
+        .description(format!(
+            "{} the session `{}`? Players on it will be disconnected.",
+            verb, name
+        ));
+    let (confirmed, handle) = confirm(ctx, prompt, verb).await?;
+    // Nothing may fail before the buttons are cleared
+    let result = embed(&ctx).await.unwrap_or_default();
+    let result = match confirmed {
+        Some(true) => {
+            let taurus = taurus_client(ctx.serenity_context()).await;
+            let response = if restart {
+                taurus.restart_session(&name).await
+            } else {
+                taurus.stop_session(&name).await
+            };
+            match response {
+                Ok(response) => {
+                    let result = result
+                        .title(format!("{} {}", done, name))
+                        .description(response);
+                    status_fields(&taurus, &name, result.clone())
+                        .await
+                        .unwrap_or(result)
+                }
+                Err(e) => result
+                    .title(format!("Failed to {} {}", verb.to_lowercase(), name))
+                    .description(e.to_string()),
+            }
+        }
+        Some(false) => result
+            .title(format!("{} {}", verb, name))
+            .description("Cancelled"),
+        None => result
+            .title(format!("{} {}", verb, name))
+            .description("Timed out, nothing was done"),
+    };
+    let reply = CreateReply::default().embed(result).components(Vec::new());
+    handle.edit(ctx, reply).await?;
+    Ok(())
+}
+
+/// Stops a session
+#[command(slash_command, prefix_command, check = "check_staff")]
+async fn stop(
+    ctx: Context<'_>,
+    #[description = "Name of the session"]
+    #[autocomplete = "autocomplete_session"]
+    name: String,
+) -> Result<(), Error> {
+    destructive(ctx, name, false).await
+}
+
+/// Restarts a session
+#[command(slash_command, prefix_command, check = "check_staff")]
+async fn restart(
+    ctx: Context<'_>,
+    #[description = "Name of the session"]
+    #[autocomplete = "autocomplete_session"]
+    name: String,
+) -> Result<(), Error> {
+    destructive(ctx, name, true).await
+}
@@ -0,0 +1,61 @@
+use std::time::UNIX_EPOCH;
+
+use timeago::Formatter;
+
+use crate::{
+    commands::prelude::*,
+    taurus::{ConnectionState, taurus_client},
+};
+
+use super::check_member;
+
+/// Commands for inspecting the connection to Taurus
+///
+/// # Arguments
+/// * `cmd` - The command to execute, which can be one of the following:
+///     - `status`: Show the state of the connection
+#[command(
+    slash_command,
+    prefix_command,
+    subcommands("status"),
+    subcommand_required,
+    check = "check_member"
+)]
+pub async fn taurus(_: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Shows the URL, authentication state, uptime and last error of the Taurus connection
+#[command(slash_command, prefix_command)]
+async fn status(ctx: Context<'_>) -> Result<(), Error> {
+    let status = taurus_client(ctx.serenity_context()).await.status().await;
+
+    let uptime = match status.state {
+        ConnectionState::Connected { since } => {
+            let mut f = Formatter::new();
+            f.num_items(3);
+            f.ago("");
+            f.convert(since.elapsed())
+        }
+        _ => "Not connected".to_string(),
+    };
+    let last_error = match status.last_error {
+        Some((error, at)) => {
+            let at = at.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+            format!("{} (<t:{}:R>)", error, at)
+        }
+        None => "None".to_string(),
+    };
+
+    let embed = embed(&ctx)
+        .await?
+        .title("Taurus Status")
+        .field("URL", status.url, false)
+        .field("State", status.state.to_string(), true)
+        .field("Authentication", status.auth.to_string(), true)
+        .field("Uptime", uptime, true)
+        .field("Last Error", last_error, false);
+    let reply = CreateReply::default().embed(embed);
+    ctx.send(reply).await?;
+    Ok(())
+}
@@ -1,6 +1,12 @@
-use std::sync::{Arc, RwLock};
+use std::time::Duration;
 
-use poise::serenity_prelude::{CreateEmbed, CreateEmbedFooter, prelude::TypeMap};
+use poise::{
+    CreateReply, ReplyHandle,
+    serenity_prelude::{
+        ButtonStyle, CreateActionRow, CreateButton, CreateEmbed, CreateEmbedFooter,
+        collector::ComponentInteractionCollector,
+    },
+};
 
 use crate::Config;
 
@@ -8,6 +14,7 @@ pub mod member;
 pub mod public;
 pub mod staff;
 pub mod ingame;
+pub mod shared;
 
 type Data = ();
 
@@ -27,7 +34,54 @@ pub async fn embed(ctx: &Context<'_>) -> Result<CreateEmbed, Error> {
     Ok(embed)
 }
 
+/// How long confirmation buttons stay active
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Asks the author to confirm an action with a pair of buttons. Returns
+/// whether they confirmed, or `None` if nobody answered in time, along with
+/// the reply so it can be replaced with the outcome.
+pub async fn confirm<'a>(
+    ctx: Context<'a>,
+    prompt: CreateEmbed,
+    action: &str,
+) -> Result<(Option<bool>, ReplyHandle<'a>), Error> {
+    let ctx_id = ctx.id();
+    let confirm_id = format!("{}confirm", ctx_id);
+    let cancel_id = format!("{}cancel", ctx_id);
+    let buttons = CreateActionRow::Buttons(vec![
+        CreateButton::new(&confirm_id)
+            .label(action)
+            .style(ButtonStyle::Danger),
+        CreateButton::new(&cancel_id)
+            .label("Cancel")
+            .style(ButtonStyle::Secondary),
+    ]);
+    let handle = ctx
+        .send(
+            CreateReply::default()
+                .embed(prompt)
+                .components(vec![buttons]),
+        )
+        .await?;
+
+    let author = ctx.author().id;
+    let ids = [confirm_id.clone(), cancel_id];
+    let press = ComponentInteractionCollector::new(ctx)
+        .filter(move |press| ids.contains(&press.data.custom_id) && press.user.id == author)
+        .timeout(CONFIRM_TIMEOUT)
+        .await;
+    let Some(press) = press else {
+        return Ok((None, handle));
+    };
+    // Acknowledge the button straight away, the action can take a while
+    press.defer(ctx.serenity_context()).await?;
+    Ok((Some(press.data.custom_id == confirm_id), handle))
+}
+
 mod prelude {
-    pub use super::{Context, Error, embed};
+    pub use super::{
+        Context, Error, confirm, embed,
+        shared::{Reply, send_reply},
+    };
     pub use poise::{CreateReply, command};
 }
@@ -1,11 +1,9 @@
-use chrono::{DateTime, Datelike, Timelike};
+use chrono::DateTime;
 use timeago::Formatter;
 
 use crate::commands::prelude::*;
 
-/// Displays the age of the Hypnos Server
-#[command(slash_command, prefix_command)]
-pub async fn age(ctx: Context<'_>) -> Result<(), Error> {
+pub async fn age_reply() -> Result<Reply, Error> {
     let mut f = Formatter::new();
     let start = DateTime::from_timestamp_millis(1569559890000).expect("Failed to parse start date");
     let now = chrono::Utc::now();
@@ -13,12 +11,12 @@ pub async fn age(ctx: Context<'_>) -> Result<(), Error> {
     f.num_items(99);
     f.ago("");
     let age = f.convert_chrono(start, now);
-    let embed = embed(&ctx)
-        .await?
-        .title("Age of the Hypnos Server")
-        .description(&age);
-    let reply = CreateReply::default().embed(embed);
-    ctx.send(reply).await?;
+    Ok(Reply::new("Age of the Hypnos Server").description(age))
+}
 
-    Ok(())
+/// Displays the age of the Hypnos Server
+#[command(slash_command, prefix_command)]
+pub async fn age(ctx: Context<'_>) -> Result<(), Error> {
+    let reply = age_reply().await?;
+    send_reply(&ctx, reply).await
 }
@@ -18,24 +18,26 @@ struct Servers {
     servers: Vec<Server>,
 }
 
-#[command(slash_command, prefix_command)]
-pub async fn hardware(ctx: Context<'_>) -> Result<(), Error> {
+pub async fn hardware_reply() -> Result<Reply, Error> {
     let hardware_file = read_to_string("data/hardware.json")
         .map_err(|e| format!("Failed to read hardware file: {}", e))?;
     let servers: Servers = serde_json::from_str(&hardware_file)
         .map_err(|e| format!("Failed to parse hardware file: {}", e))?;
-    let mut embed = embed(&ctx).await?.title("Hypnos Server Hardware");
+    let mut reply = Reply::new("Hypnos Server Hardware");
     for server in &servers.servers {
-        embed = embed
+        reply = reply
             .field(&server.name, "", false)
             .field("CPU", &server.cpu, true)
             .field("RAM", &server.ram, true)
             .field("Drives", &server.drives, true)
             .field("GPU", &server.gpu, true);
     }
+    Ok(reply)
+}
 
-    let reply = CreateReply::default().embed(embed);
-    ctx.send(reply).await?;
-
-    Ok(())
+/// Shows the hardware the Hypnos servers run on
+#[command(slash_command, prefix_command)]
+pub async fn hardware(ctx: Context<'_>) -> Result<(), Error> {
+    let reply = hardware_reply().await?;
+    send_reply(&ctx, reply).await
 }
@@ -0,0 +1,55 @@
+use crate::{
+    commands::prelude::*,
+    links::{CODE_LIFETIME, Links},
+};
+
+/// Links your Discord account to your Minecraft account
+#[command(slash_command, prefix_command)]
+pub async fn link(ctx: Context<'_>) -> Result<(), Error> {
+    let (code, linked) = {
+        let mut data = ctx.serenity_context().data.write().await;
+        let links = data
+            .get_mut::<Links>()
+            .expect("Links not found in context data");
+        let linked = links.player(ctx.author().id).map(str::to_string);
+        (links.issue_code(ctx.author().id), linked)
+    };
+
+    let mut desc = format!(
+        "Type `{}` in Minecraft chat within {} minutes to finish linking.",
+        code,
+        CODE_LIFETIME.as_secs() / 60
+    );
+    if let Some(player) = linked {
+        desc.push_str(&format!("\nThis replaces your link to `{}`.", player));
+    }
+    let embed = embed(&ctx)
+        .await?
+        .title("Link your account")
+        .description(desc);
+    ctx.send(CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(())
+}
+
+/// Unlinks your Discord account from your Minecraft account
+#[command(slash_command, prefix_command)]
+pub async fn unlink(ctx: Context<'_>) -> Result<(), Error> {
+    let removed = {
+        let mut data = ctx.serenity_context().data.write().await;
+        data.get_mut::<Links>()
+            .expect("Links not found in context data")
+            .remove(ctx.author().id)?
+    };
+    let desc = match removed {
+        Some(player) => format!("Unlinked from `{}`.", player),
+        None => "Your account is not linked.".to_string(),
+    };
+    let embed = embed(&ctx)
+        .await?
+        .title("Unlink your account")
+        .description(desc);
+    ctx.send(CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(())
+}
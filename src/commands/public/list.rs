@@ -1,28 +1,30 @@
-use crate::taurus::fetch_latest_with_type;
-use crate::{TaurusChannel};
+use poise::serenity_prelude as serenity;
+
+use crate::taurus::taurus_client;
 
 use crate::commands::prelude::*;
 
-//// Lists the online players on the Hypnos server
-#[command(slash_command, prefix_command)]
-pub async fn list(ctx: Context<'_>) -> Result<(), Error> {
-    let cache = {
-        let data = ctx.serenity_context().data.read().await;
-        let (sender, cache) = data
-            .get::<TaurusChannel>()
-            .expect("TaurusChannel not found in context data");
-        sender.send("LIST".to_owned()).await?;
-        cache.clone()
-    };
-    let res = &fetch_latest_with_type(cache, "LIST").await?[5..].replace(':', ": ");
-    let desc = if res.len() > 1 {
-        format!("```{}```", res)
+pub async fn list_reply(ctx: &serenity::Context) -> Result<Reply, Error> {
+    let taurus = taurus_client(ctx).await;
+    let servers = taurus.list().await?;
+    let online = servers
+        .iter()
+        .filter(|s| !s.players.is_empty())
+        .map(|s| format!("{}: {}", s.server, s.players.join(", ")))
+        .collect::<Vec<_>>();
+    let desc = if !online.is_empty() {
+        online.join("\n")
     } else {
-        "```No players are currently online.```".to_string()
+        "No players are currently online.".to_string()
     };
-    let embed = embed(&ctx).await?.title("Online players").description(desc);
+    Ok(Reply::new("Online players")
+        .description(desc)
+        .preformatted())
+}
 
-    let reply = CreateReply::default().embed(embed);
-    ctx.send(reply).await?;
-    Ok(())
+/// Lists the online players on the Hypnos server
+#[command(slash_command, prefix_command)]
+pub async fn list(ctx: Context<'_>) -> Result<(), Error> {
+    let reply = list_reply(ctx.serenity_context()).await?;
+    send_reply(&ctx, reply).await
 }
@@ -4,18 +4,22 @@ mod invite;
 mod list;
 mod score;
 mod iscore;
+mod link;
 mod tps;
 mod website;
+mod whois;
 mod worldsize;
 
 // export
-pub use age::age;
-pub use hardware::hardware;
+pub use age::{age, age_reply};
+pub use hardware::{hardware, hardware_reply};
 pub use invite::invite;
-pub use list::list;
+pub use list::{list, list_reply};
 pub use score::score;
 pub use iscore::iscore;
-pub use tps::tps;
+pub use link::{link, unlink};
+pub use tps::{tps, tps_reply, world_tps};
 pub use website::website;
-pub use worldsize::worldsize;
-pub use score::{get_scoreboard, search_scoreboards, SearchFunction};
+pub use whois::whois;
+pub use worldsize::{worldsize, worldsize_reply};
+pub use score::{get_scoreboard, paginate, search_scoreboards, SearchFunction};
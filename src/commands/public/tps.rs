@@ -1,66 +1,76 @@
 use std::{
     io::{BufReader, Read},
-    path::PathBuf,
+    path::Path,
 };
 
-use flate2::{GzBuilder, bufread::GzDecoder};
+use flate2::bufread::GzDecoder;
 use valence_nbt::{Value, from_binary};
 
+use poise::serenity_prelude as serenity;
+
 use crate::{Config, commands::prelude::*};
 
-#[command(slash_command, prefix_command)]
-pub async fn tps(ctx: Context<'_>) -> Result<(), Error> {
+/// Seconds between world autosaves
+const AUTOSAVE_INTERVAL: f64 = 45.0;
+
+fn last_played(path: &Path) -> Result<Option<i64>, String> {
+    let file = std::fs::File::open(path).map_err(|_| "unable to access world files")?;
+    let reader = BufReader::new(file);
+    let mut buf = Vec::new();
+    let mut decoder = GzDecoder::new(reader);
+    decoder
+        .read_to_end(&mut buf)
+        .map_err(|_| "failed to read world files")?;
+    let (nbt, _) =
+        from_binary::<String>(&mut buf.as_slice()).map_err(|_| "failed to parse world files")?;
+    let Some(Value::Compound(data)) = nbt.get("Data") else {
+        return Ok(None);
+    };
+    let Some(Value::Long(last_played)) = data.get("LastPlayed") else {
+        return Ok(None);
+    };
+    Ok(Some(*last_played))
+}
+
+/// Estimates a world's TPS from how far apart its last two autosaves were,
+/// or `None` if the world has not saved twice yet
+pub fn world_tps(world_path: &Path) -> Result<Option<f64>, String> {
+    let new = world_path.join("level.dat");
+    let old = world_path.join("level.dat_old");
+    if !new.exists() || !old.exists() {
+        return Ok(None);
+    }
+    let (Some(new), Some(old)) = (last_played(&new)?, last_played(&old)?) else {
+        return Ok(None);
+    };
+    let seconds = (new - old) as f64 / 1000.0;
+    if seconds <= 0.0 {
+        return Ok(None);
+    }
+    Ok(Some((AUTOSAVE_INTERVAL / seconds * 20.0).clamp(0.0, 20.0)))
+}
+
+pub async fn tps_reply(ctx: &serenity::Context) -> Result<Reply, Error> {
     let worlds = {
-        let data = ctx.serenity_context().data.read().await;
+        let data = ctx.data.read().await;
         let config = data
             .get::<Config>()
-            .expect("TaurusChannel not found in context data");
+            .expect("Config not found in context data");
         config.worlds.clone()
     };
 
-    let mut embed = embed(&ctx).await?.title("Hypnos Server TPS");
+    let mut reply = Reply::new("Hypnos Server TPS");
     for world in worlds {
-        let mut new = None;
-        for file_name in ["level.dat", "leve.dat_old"] {
-            let path = PathBuf::from(&world.path).join(file_name);
-            if !path.exists() {
-                continue;
-            }
-            let file =
-                std::fs::File::open(&path).map_err(|e| format!("unable to access world files"))?;
-            let reader = BufReader::new(file);
-            let mut buf = Vec::new();
-            let mut decoder = GzDecoder::new(reader);
-            decoder
-                .read_to_end(&mut buf)
-                .map_err(|e| format!("failed to read world files"))?;
-            let (nbt, _) = from_binary::<String>(&mut buf.as_slice())
-                .map_err(|e| format!("failed to parse world files"))?;
-            let Some(Value::Compound(data)) = nbt.get("Data") else {
-                continue;
-            };
-            let Some(Value::Long(last_played_value)) = data.get("LastPlayed") else {
-                continue;
-            };
-            if file_name == "level.dat" {
-                new = Some(*last_played_value);
-            } else {
-                let Some(new) = new else {
-                    continue;
-                };
-                let mut tps = (45.0 / ((new - last_played_value) as f64 / 1000.0)).round() * 20.0;
-                if tps < 0.0 {
-                    tps = 0.0;
-                }
-                if tps > 20.0 {
-                    tps = 20.0;
-                }
-                embed = embed.field(world.name.clone(), format!("{:.2} TPS", tps), false);
-            }
+        if let Some(tps) = world_tps(Path::new(&world.path))? {
+            reply = reply.field(world.name, format!("{:.2} TPS", tps), false);
         }
     }
-    let reply = CreateReply::default().embed(embed);
+    Ok(reply)
+}
 
-    ctx.send(reply).await?;
-    Ok(())
+/// Shows the estimated TPS of each world
+#[command(slash_command, prefix_command)]
+pub async fn tps(ctx: Context<'_>) -> Result<(), Error> {
+    let reply = tps_reply(ctx.serenity_context()).await?;
+    send_reply(&ctx, reply).await
 }
@@ -0,0 +1,36 @@
+use poise::serenity_prelude::User;
+
+use crate::{commands::prelude::*, links::Links};
+
+/// Looks up whose Minecraft or Discord account is whose
+///
+/// # Arguments
+/// * `user` - The Discord user to find the Minecraft account of
+/// * `player` - The Minecraft player to find the Discord account of
+#[command(slash_command, prefix_command)]
+pub async fn whois(
+    ctx: Context<'_>,
+    #[description = "Discord user to look up"] user: Option<User>,
+    #[description = "Minecraft player to look up"] player: Option<String>,
+) -> Result<(), Error> {
+    let desc = {
+        let data = ctx.serenity_context().data.read().await;
+        let links = data
+            .get::<Links>()
+            .expect("Links not found in context data");
+        match (user, player) {
+            (Some(user), _) => match links.player(user.id) {
+                Some(player) => format!("<@{}> plays as `{}`", user.id, player),
+                None => format!("<@{}> has not linked a Minecraft account", user.id),
+            },
+            (None, Some(player)) => match links.user(&player) {
+                Some(user) => format!("`{}` is <@{}>", player, user),
+                None => format!("`{}` has not linked a Discord account", player),
+            },
+            (None, None) => "Give a Discord user or a Minecraft player to look up".to_string(),
+        }
+    };
+    let embed = embed(&ctx).await?.title("Who is").description(desc);
+    ctx.send(CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
@@ -1,30 +1,27 @@
-use std::{
-    io::{BufReader, Read},
-    path::PathBuf,
-};
-
-use flate2::{GzBuilder, bufread::GzDecoder};
-use valence_nbt::{Value, from_binary};
+use poise::serenity_prelude as serenity;
 
 use crate::{Config, commands::prelude::*};
 
-#[command(slash_command, prefix_command)]
-pub async fn worldsize(ctx: Context<'_>) -> Result<(), Error> {
+pub async fn worldsize_reply(ctx: &serenity::Context) -> Result<Reply, Error> {
     let worlds = {
-        let data = ctx.serenity_context().data.read().await;
+        let data = ctx.data.read().await;
         let config = data
             .get::<Config>()
             .expect("Config not found in context data");
         config.worlds.clone()
     };
 
-    let mut embed = embed(&ctx).await?.title("World File Size");
+    let mut reply = Reply::new("World File Size");
     for world in worlds {
-        let child_process = std::process::Command::new("du")
-            .arg("-sh")
-            .arg(&world.path)
-            .output()
-            .map_err(|_e| format!("Probably not good"))?;
+        // du can take a while on large worlds, keep it off the async workers
+        let child_process = tokio::task::spawn_blocking(move || {
+            std::process::Command::new("du")
+                .arg("-sh")
+                .arg(&world.path)
+                .output()
+        })
+        .await?
+        .map_err(|e| format!("Failed to run du: {}", e))?;
         if !child_process.status.success() {
             return Err("Failed to get world size".into());
         }
@@ -33,10 +30,14 @@ pub async fn worldsize(ctx: Context<'_>) -> Result<(), Error> {
             .next()
             .unwrap_or("Unknown")
             .to_string();
-        embed = embed.field(world.name.clone(), size.to_string(), false);
+        reply = reply.field(world.name.clone(), size, false);
     }
-    let reply = CreateReply::default().embed(embed);
+    Ok(reply)
+}
 
-    ctx.send(reply).await?;
-    Ok(())
+/// Shows how much disk space each world takes up
+#[command(slash_command, prefix_command)]
+pub async fn worldsize(ctx: Context<'_>) -> Result<(), Error> {
+    let reply = worldsize_reply(ctx.serenity_context()).await?;
+    send_reply(&ctx, reply).await
 }
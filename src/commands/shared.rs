@@ -0,0 +1,204 @@
+use crate::{
+    Config,
+    links::Links,
+    taurus::{Colour, TextComponent},
+};
+use futures::future::BoxFuture;
+use poise::{
+    CreateReply,
+    serenity_prelude::{self as serenity, CreateEmbed},
+};
+
+use super::{
+    Context, Error, embed,
+    public::{age_reply, hardware_reply, list_reply, tps_reply, worldsize_reply},
+};
+
+/// One field of a [`Reply`], shown as an embed field on Discord and as a
+/// `name: value` line in-game
+pub struct Field {
+    pub name: String,
+    pub value: String,
+    pub inline: bool,
+}
+
+/// What a shared command answers with, before it is rendered for Discord or
+/// Minecraft
+pub struct Reply {
+    pub title: String,
+    pub description: Option<String>,
+    /// Show the description in a code block on Discord
+    pub preformatted: bool,
+    pub fields: Vec<Field>,
+}
+
+impl Reply {
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            description: None,
+            preformatted: false,
+            fields: Vec::new(),
+        }
+    }
+
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    pub fn preformatted(mut self) -> Self {
+        self.preformatted = true;
+        self
+    }
+
+    pub fn field(
+        mut self,
+        name: impl Into<String>,
+        value: impl Into<String>,
+        inline: bool,
+    ) -> Self {
+        self.fields.push(Field {
+            name: name.into(),
+            value: value.into(),
+            inline,
+        });
+        self
+    }
+
+    /// Fills in an embed already carrying the bot's colour and footer
+    pub fn to_embed(&self, embed: CreateEmbed) -> CreateEmbed {
+        let mut embed = embed.title(&self.title);
+        if let Some(description) = &self.description {
+            embed = match self.preformatted {
+                true => embed.description(format!("```{}```", description)),
+                false => embed.description(description),
+            };
+        }
+        for field in &self.fields {
+            embed = embed.field(&field.name, &field.value, field.inline);
+        }
+        embed
+    }
+
+    /// The reply as a tellraw component
+    pub fn to_tellraw(&self) -> TextComponent {
+        // Children inherit the style of their parent, so the root stays plain
+        let mut root = TextComponent::new("")
+            .child(TextComponent::new(&self.title).bold().colour(Colour::Gold));
+        if let Some(description) = &self.description {
+            root =
+                root.child(TextComponent::new(format!("\n{}", description)).colour(Colour::White));
+        }
+        for field in &self.fields {
+            if field.value.is_empty() {
+                root = root.child(
+                    TextComponent::new(format!("\n{}", field.name))
+                        .bold()
+                        .colour(Colour::Yellow),
+                );
+                continue;
+            }
+            root = root
+                .child(TextComponent::new(format!("\n{}: ", field.name)).colour(Colour::Gray))
+                .child(TextComponent::new(&field.value).colour(Colour::White));
+        }
+        root
+    }
+}
+
+/// Who may run a shared command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    Public,
+    /// Only members can join the servers, so this is everyone in-game
+    Member,
+    /// In-game, only players linked to a staff account
+    Staff,
+}
+
+type Handler =
+    for<'a> fn(&'a serenity::Context, &'a [&'a str]) -> BoxFuture<'a, Result<Reply, Error>>;
+
+/// A command that can be run from Discord and from in-game chat
+pub struct SharedCommand {
+    pub name: &'static str,
+    /// Argument names, shown in usage messages
+    pub args: &'static [&'static str],
+    pub permission: Permission,
+    pub handler: Handler,
+}
+
+impl SharedCommand {
+    pub fn usage(&self, prefix: &str) -> String {
+        let mut usage = format!("{}{}", prefix, self.name);
+        for arg in self.args {
+            usage.push_str(&format!(" <{}>", arg));
+        }
+        usage
+    }
+
+    /// Whether a Minecraft player may run this command
+    pub async fn allowed_ingame(&self, ctx: &serenity::Context, player: &str) -> bool {
+        match self.permission {
+            Permission::Public | Permission::Member => true,
+            Permission::Staff => {
+                let data = ctx.data.read().await;
+                let config = data.get::<Config>().expect("Config not found");
+                let links = data.get::<Links>().expect("Links not found");
+                links
+                    .user(player)
+                    .is_some_and(|user| config.staff.contains(&user.get()))
+            }
+        }
+    }
+
+    pub async fn run(&self, ctx: &serenity::Context, args: &[&str]) -> Result<Reply, Error> {
+        (self.handler)(ctx, args).await
+    }
+}
+
+pub const COMMANDS: &[SharedCommand] = &[
+    SharedCommand {
+        name: "list",
+        args: &[],
+        permission: Permission::Public,
+        handler: |ctx, _| Box::pin(list_reply(ctx)),
+    },
+    SharedCommand {
+        name: "tps",
+        args: &[],
+        permission: Permission::Public,
+        handler: |ctx, _| Box::pin(tps_reply(ctx)),
+    },
+    SharedCommand {
+        name: "age",
+        args: &[],
+        permission: Permission::Public,
+        handler: |_, _| Box::pin(age_reply()),
+    },
+    SharedCommand {
+        name: "worldsize",
+        args: &[],
+        permission: Permission::Public,
+        handler: |ctx, _| Box::pin(worldsize_reply(ctx)),
+    },
+    SharedCommand {
+        name: "hardware",
+        args: &[],
+        permission: Permission::Public,
+        handler: |_, _| Box::pin(hardware_reply()),
+    },
+];
+
+/// Looks up a shared command by name
+pub fn find(name: &str) -> Option<&'static SharedCommand> {
+    COMMANDS.iter().find(|command| command.name == name)
+}
+
+/// Sends a shared command's reply as an embed
+pub async fn send_reply(ctx: &Context<'_>, reply: Reply) -> Result<(), Error> {
+    let embed = reply.to_embed(embed(ctx).await?);
+    ctx.send(CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
@@ -0,0 +1,111 @@
+use poise::serenity_prelude::{CreateEmbed, Timestamp};
+
+use crate::{commands::prelude::*, config::Config, taurus::taurus_client};
+
+use super::{audit, check_staff};
+
+/// Longest console response shown in an embed field
+const RESPONSE_LIMIT: usize = 1000;
+
+/// Lowercases a console command and drops the leading `/` and `minecraft:`
+fn normalise(command: &str) -> String {
+    let command = command.trim().trim_start_matches('/');
+    let command = command.strip_prefix("minecraft:").unwrap_or(command);
+    command
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// The denylist entry blocking a command, if any. Commands nested in
+/// `execute ... run` are checked as well.
+pub(crate) fn denied_by<'a>(denylist: &'a [String], command: &str) -> Option<&'a str> {
+    let command = normalise(command);
+    let words = command.split(' ').collect::<Vec<_>>();
+    let nested = words
+        .iter()
+        .enumerate()
+        .filter(|(_, word)| **word == "run")
+        .map(|(i, _)| normalise(&words[i + 1..].join(" ")));
+    let commands = std::iter::once(command.clone())
+        .chain(nested)
+        .collect::<Vec<_>>();
+    denylist.iter().map(String::as_str).find(|entry| {
+        let entry = normalise(entry);
+        commands
+            .iter()
+            .any(|command| *command == entry || command.starts_with(&format!("{} ", entry)))
+    })
+}
+
+pub(crate) fn code_block(text: &str) -> String {
+    let text = if text.trim().is_empty() {
+        "No response"
+    } else {
+        text
+    };
+    let truncated = match text.char_indices().nth(RESPONSE_LIMIT) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    };
+    format!("```{}```", truncated.replace("```", "'''"))
+}
+
+/// Runs a console command on a server through Taurus
+///
+/// # Arguments
+/// * `server` - The Taurus session to run the command on
+/// * `command` - The console command, without a leading `/`
+#[command(slash_command, prefix_command, check = "check_staff")]
+pub async fn execute(
+    ctx: Context<'_>,
+    #[description = "Server to run the command on"] server: String,
+    #[description = "Console command to run"]
+    #[rest]
+    command: String,
+) -> Result<(), Error> {
+    let (denied, known) = {
+        let data = ctx.serenity_context().data.read().await;
+        let config = data
+            .get::<Config>()
+            .expect("Config not found in context data");
+        (
+            denied_by(&config.rcon_denylist, &command).map(str::to_string),
+            config.worlds.iter().any(|world| world.name == server),
+        )
+    };
+
+    let taurus = taurus_client(ctx.serenity_context()).await;
+    let result = match &denied {
+        Some(entry) => Err(format!("Denied by `{}` on the denylist", entry)),
+        None if !known => Err(format!("Unknown server `{}`", server)),
+        None => taurus
+            .rcon(&server, &command)
+            .await
+            .map_err(|e| e.to_string()),
+    };
+
+    let (title, outcome) = match &result {
+        Ok(response) => (format!("Executed on {}", server), code_block(response)),
+        Err(e) if denied.is_some() => ("Command denied".to_string(), e.clone()),
+        Err(e) => (format!("Failed to execute on {}", server), e.clone()),
+    };
+    let embed = embed(&ctx)
+        .await?
+        .title(title)
+        .field("Command", code_block(&command), false)
+        .field("Response", &outcome, false);
+    ctx.send(CreateReply::default().embed(embed)).await?;
+
+    let author = ctx.author();
+    let entry = CreateEmbed::new()
+        .title("Console command")
+        .field("Staff", format!("<@{}> ({})", author.id, author.name), true)
+        .field("Server", &server, true)
+        .field("Command", code_block(&command), false)
+        .field("Result", outcome, false)
+        .timestamp(Timestamp::now());
+    audit(ctx, entry).await;
+    Ok(())
+}
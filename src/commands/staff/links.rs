@@ -0,0 +1,92 @@
+use poise::serenity_prelude::User;
+
+use crate::{commands::prelude::*, links::Links, taurus::protocol::is_player_name};
+
+use super::{audit, check_staff};
+
+/// Overrides Discord to Minecraft account links
+///
+/// # Arguments
+/// * `cmd` - The command to execute, which can be one of the following:
+///     - `set`: Link a Discord user to a Minecraft player
+///     - `remove`: Remove a Discord user's link
+#[command(
+    slash_command,
+    prefix_command,
+    subcommands("set", "remove"),
+    subcommand_required,
+    check = "check_staff"
+)]
+pub async fn links(_: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Links a Discord user to a Minecraft player without a code
+#[command(slash_command, prefix_command)]
+async fn set(
+    ctx: Context<'_>,
+    #[description = "Discord user"] user: User,
+    #[description = "Minecraft username"] player: String,
+) -> Result<(), Error> {
+    if !is_player_name(&player) {
+        let embed = embed(&ctx)
+            .await?
+            .title("Invalid player name")
+            .description(format!("`{}` is not a Minecraft username", player));
+        ctx.send(CreateReply::default().embed(embed)).await?;
+        return Ok(());
+    }
+    {
+        let mut data = ctx.serenity_context().data.write().await;
+        data.get_mut::<Links>()
+            .expect("Links not found in context data")
+            .set(user.id, &player)?;
+    }
+    let desc = format!("Linked <@{}> to `{}`", user.id, player);
+    let embed = embed(&ctx)
+        .await?
+        .title("Account linked")
+        .description(&desc);
+    ctx.send(CreateReply::default().embed(embed.clone()))
+        .await?;
+    let author = ctx.author();
+    audit(
+        ctx,
+        embed.description(format!("{}\nBy <@{}> ({})", desc, author.id, author.name)),
+    )
+    .await;
+    Ok(())
+}
+
+/// Removes a Discord user's link
+#[command(slash_command, prefix_command)]
+async fn remove(ctx: Context<'_>, #[description = "Discord user"] user: User) -> Result<(), Error> {
+    let removed = {
+        let mut data = ctx.serenity_context().data.write().await;
+        data.get_mut::<Links>()
+            .expect("Links not found in context data")
+            .remove(user.id)?
+    };
+    let Some(player) = removed else {
+        let embed = embed(&ctx)
+            .await?
+            .title("Not linked")
+            .description(format!("<@{}> has no linked account", user.id));
+        ctx.send(CreateReply::default().embed(embed)).await?;
+        return Ok(());
+    };
+    let desc = format!("Unlinked <@{}> from `{}`", user.id, player);
+    let embed = embed(&ctx)
+        .await?
+        .title("Account unlinked")
+        .description(&desc);
+    ctx.send(CreateReply::default().embed(embed.clone()))
+        .await?;
+    let author = ctx.author();
+    audit(
+        ctx,
+        embed.description(format!("{}\nBy <@{}> ({})", desc, author.id, author.name)),
+    )
+    .await;
+    Ok(())
+}
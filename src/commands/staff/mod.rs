@@ -1,19 +1,29 @@
+mod execute;
+mod links;
+mod schedule;
+mod whitelist;
+
+pub use execute::execute;
+pub use links::links;
+pub use schedule::schedule;
+pub use whitelist::whitelist;
+
+pub(crate) use execute::{code_block, denied_by};
+
+use poise::serenity_prelude::{self as serenity, ChannelId, CreateEmbed, CreateMessage};
+
 use crate::config::Config;
 
 use super::prelude::*;
 
-pub(self) async fn check_staff(ctx: Context<'_>) -> Result<bool, Error> {
+pub(crate) async fn check_staff(ctx: Context<'_>) -> Result<bool, Error> {
     {
         let data = ctx.serenity_context().data.read().await;
         let staff = &data.get::<Config>()
             .expect("Config not found in context data")
             .staff;
-        
-        let member = ctx
-            .author_member()
-            .await
-            .expect("Failed to get author member");
-        if staff.contains(&member.user.id.into()) {
+        // Works in DMs too, where there is no member
+        if staff.contains(&ctx.author().id.get()) {
             return Ok(true);
         }
     };
@@ -21,4 +31,25 @@ pub(self) async fn check_staff(ctx: Context<'_>) -> Result<bool, Error> {
     ctx.send(CreateReply::default().content("Staff only :sunglasses:"))
         .await?;
     Ok(false)
-}
\ No newline at end of file
+}
+
+/// Logs a staff action to the audit channel, or the staff channel if there is none
+async fn audit(ctx: Context<'_>, entry: CreateEmbed) {
+    write_audit(ctx.serenity_context(), entry).await;
+}
+
+/// Logs a staff action taken outside of a command
+pub(crate) async fn write_audit(ctx: &serenity::Context, entry: CreateEmbed) {
+    let channel = {
+        let data = ctx.data.read().await;
+        let config = data.get::<Config>().expect("Config not found in context data");
+        config.audit_channel.or(config.staff_channel)
+    };
+    let Some(channel) = channel else {
+        return;
+    };
+    let message = CreateMessage::new().embed(entry);
+    if let Err(e) = ChannelId::new(channel).send_message(&ctx.http, message).await {
+        println!("ERROR: Failed to write to the audit channel: {}", e);
+    }
+}
@@ -0,0 +1,182 @@
+use chrono::Local;
+
+use crate::{
+    backup_schedule::{BackupSchedule, Schedules, parse_cron, render_name},
+    commands::prelude::*,
+};
+
+use super::{audit, check_staff};
+
+/// Manages scheduled backups
+///
+/// # Arguments
+/// * `cmd` - The command to execute, which can be one of the following:
+///     - `add`: Schedule a backup
+///     - `remove`: Remove a schedule
+///     - `list`: Show the schedules and when they next run
+#[command(
+    slash_command,
+    prefix_command,
+    subcommands("add", "remove", "list"),
+    subcommand_required,
+    check = "check_staff"
+)]
+pub async fn schedule(_: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Schedules a backup
+///
+/// # Arguments
+/// * `name` - Name of the schedule
+/// * `cron` - When to take backups, e.g. `0 4 * * *` for 04:00 every day
+/// * `template` - Name of each backup, e.g. `smp-%Y-%m-%d`
+/// * `keep` - Backups from this schedule to keep, 0 keeps them all
+#[command(slash_command, prefix_command)]
+async fn add(
+    ctx: Context<'_>,
+    #[description = "Name of the schedule"] name: String,
+    #[description = "Cron expression, e.g. `0 4 * * *`"] cron: String,
+    #[description = "Backup name with date fields, e.g. `smp-%Y-%m-%d`"] template: String,
+    #[description = "Backups to keep, 0 keeps them all"] keep: Option<usize>,
+) -> Result<(), Error> {
+    let parsed = parse_cron(&cron)?;
+    let mut upcoming = parsed.upcoming(Local);
+    let next = upcoming.next().ok_or("The cron expression never runs")?;
+    let example = render_name(&template, &next)?;
+    // The second backup would replace the first, leaving only one
+    if let Some(after) = upcoming.next()
+        && render_name(&template, &after)? == example
+    {
+        return Err(format!(
+            "`{}` names two backups in a row `{}`, add date fields such as `%H`",
+            template, example
+        )
+        .into());
+    }
+    let schedule = BackupSchedule {
+        name: name.clone(),
+        cron: cron.clone(),
+        template,
+        keep: keep.unwrap_or(0),
+        created: Vec::new(),
+    };
+    let keep = schedule.keep;
+    {
+        let mut data = ctx.serenity_context().data.write().await;
+        data.get_mut::<Schedules>()
+            .expect("Schedules not found in context data")
+            .add(schedule)?;
+    }
+
+    let keep = match keep {
+        0 => "All".to_string(),
+        keep => keep.to_string(),
+    };
+    let embed = embed(&ctx)
+        .await?
+        .title(format!("Scheduled {}", name))
+        .field("Cron", format!("`{}`", cron), true)
+        .field("Keep", keep, true)
+        .field(
+            "Next backup",
+            format!("`{}` <t:{}:R>", example, next.timestamp()),
+            false,
+        );
+    ctx.send(CreateReply::default().embed(embed.clone()))
+        .await?;
+    let author = ctx.author();
+    audit(
+        ctx,
+        embed.description(format!("By <@{}> ({})", author.id, author.name)),
+    )
+    .await;
+    Ok(())
+}
+
+async fn autocomplete_schedule(ctx: Context<'_>, partial: &str) -> Vec<String> {
+    let data = ctx.serenity_context().data.read().await;
+    data.get::<Schedules>()
+        .expect("Schedules not found in context data")
+        .schedules()
+        .iter()
+        .map(|schedule| schedule.name.clone())
+        .filter(|name| name.contains(partial))
+        .take(25)
+        .collect()
+}
+
+/// Removes a backup schedule, keeping the backups it already took
+#[command(slash_command, prefix_command)]
+async fn remove(
+    ctx: Context<'_>,
+    #[description = "Name of the schedule"]
+    #[autocomplete = "autocomplete_schedule"]
+    name: String,
+) -> Result<(), Error> {
+    let removed = {
+        let mut data = ctx.serenity_context().data.write().await;
+        data.get_mut::<Schedules>()
+            .expect("Schedules not found in context data")
+            .remove(&name)?
+    };
+    let desc = match removed {
+        Some(_) => format!("Removed the schedule `{}`", name),
+        None => format!("There is no schedule named `{}`", name),
+    };
+    let embed = embed(&ctx)
+        .await?
+        .title("Remove schedule")
+        .description(&desc);
+    ctx.send(CreateReply::default().embed(embed.clone()))
+        .await?;
+    let author = ctx.author();
+    audit(
+        ctx,
+        embed.description(format!("{}\nBy <@{}> ({})", desc, author.id, author.name)),
+    )
+    .await;
+    Ok(())
+}
+
+/// Shows the backup schedules and when they next run
+#[command(slash_command, prefix_command)]
+async fn list(ctx: Context<'_>) -> Result<(), Error> {
+    let schedules = {
+        let data = ctx.serenity_context().data.read().await;
+        data.get::<Schedules>()
+            .expect("Schedules not found in context data")
+            .schedules()
+            .to_vec()
+    };
+    let mut embed = embed(&ctx).await?.title("Backup schedules");
+    if schedules.is_empty() {
+        embed = embed.description("No backups are scheduled");
+    }
+    for schedule in schedules {
+        let next = parse_cron(&schedule.cron)
+            .ok()
+            .and_then(|cron| cron.upcoming(Local).next())
+            .map_or("never".to_string(), |next| {
+                format!("<t:{}:R>", next.timestamp())
+            });
+        let keep = match schedule.keep {
+            0 => "all".to_string(),
+            keep => keep.to_string(),
+        };
+        embed = embed.field(
+            schedule.name,
+            format!(
+                "`{}` as `{}`\nNext: {}, keeping {} ({} so far)",
+                schedule.cron,
+                schedule.template,
+                next,
+                keep,
+                schedule.created.len()
+            ),
+            false,
+        );
+    }
+    ctx.send(CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
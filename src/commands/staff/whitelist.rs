@@ -0,0 +1,204 @@
+use crate::{
+    commands::prelude::*,
+    config::Config,
+    scoreboard::Scoreboards,
+    taurus::{protocol::is_player_name, taurus_client},
+    whitelist_sync,
+};
+
+use super::{audit, check_staff};
+
+/// Manages the server whitelist
+///
+/// # Arguments
+/// * `cmd` - The command to execute, which can be one of the following:
+///     - `add`: Whitelist a player
+///     - `remove`: Remove a player from the whitelist
+///     - `list`: Show the whitelisted players
+///     - `sync`: Compare the whitelist with the member role
+#[command(
+    slash_command,
+    prefix_command,
+    subcommands("add", "remove", "list", "sync"),
+    subcommand_required,
+    check = "check_staff"
+)]
+pub async fn whitelist(_: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Runs `whitelist <action> <player>` on every server, or only on `server`
+async fn change(
+    ctx: Context<'_>,
+    action: &str,
+    player: String,
+    server: Option<String>,
+) -> Result<(), Error> {
+    if !is_player_name(&player) {
+        let embed = embed(&ctx)
+            .await?
+            .title("Invalid player name")
+            .description(format!("`{}` is not a Minecraft username", player));
+        ctx.send(CreateReply::default().embed(embed)).await?;
+        return Ok(());
+    }
+    let servers = {
+        let data = ctx.serenity_context().data.read().await;
+        let config = data
+            .get::<Config>()
+            .expect("Config not found in context data");
+        // Only configured servers, the name goes straight into the frame
+        config
+            .worlds
+            .iter()
+            .map(|world| world.name.clone())
+            .filter(|name| server.as_ref().is_none_or(|server| server == name))
+            .collect::<Vec<_>>()
+    };
+    if let Some(server) = &server
+        && servers.is_empty()
+    {
+        let embed = embed(&ctx)
+            .await?
+            .title("Unknown server")
+            .description(format!("`{}` is not a configured server", server));
+        ctx.send(CreateReply::default().embed(embed)).await?;
+        return Ok(());
+    }
+
+    let taurus = taurus_client(ctx.serenity_context()).await;
+    let command = format!("whitelist {} {}", action, player);
+    let mut embed = embed(&ctx)
+        .await?
+        .title(format!("Whitelist {} {}", action, player));
+    let mut audit_entry = embed.clone();
+    for server in &servers {
+        let response = match taurus.rcon(server, &command).await {
+            Ok(response) if response.trim().is_empty() => "No response".to_string(),
+            Ok(response) => response,
+            Err(e) => format!("Failed: {}", e),
+        };
+        embed = embed.field(server, &response, false);
+        audit_entry = audit_entry.field(server, response, false);
+    }
+
+    {
+        let mut data = ctx.serenity_context().data.write().await;
+        let scoreboards = data
+            .get_mut::<Scoreboards>()
+            .expect("Scoreboards not found in context data");
+        if let Err(e) = scoreboards.load_whitelist() {
+            println!("ERROR: Failed to reload whitelist: {}", e);
+        }
+    }
+
+    ctx.send(CreateReply::default().embed(embed)).await?;
+    let author = ctx.author();
+    audit_entry = audit_entry.description(format!("By <@{}> ({})", author.id, author.name));
+    audit(ctx, audit_entry).await;
+    Ok(())
+}
+
+/// Whitelists a player on every server
+#[command(slash_command, prefix_command)]
+async fn add(
+    ctx: Context<'_>,
+    #[description = "Minecraft username"] player: String,
+    #[description = "Only whitelist on this server"] server: Option<String>,
+) -> Result<(), Error> {
+    change(ctx, "add", player, server).await
+}
+
+/// Removes a player from the whitelist on every server
+#[command(slash_command, prefix_command)]
+async fn remove(
+    ctx: Context<'_>,
+    #[description = "Minecraft username"] player: String,
+    #[description = "Only remove from this server"] server: Option<String>,
+) -> Result<(), Error> {
+    change(ctx, "remove", player, server).await
+}
+
+/// Shows the players in `whitelist.json`
+#[command(slash_command, prefix_command)]
+async fn list(ctx: Context<'_>) -> Result<(), Error> {
+    let players = {
+        let mut data = ctx.serenity_context().data.write().await;
+        let scoreboards = data
+            .get_mut::<Scoreboards>()
+            .expect("Scoreboards not found in context data");
+        scoreboards.load_whitelist()?;
+        let mut players = scoreboards
+            .get_whitelist()
+            .iter()
+            .cloned()
+            .collect::<Vec<_>>();
+        players.sort_by_key(|player| player.to_lowercase());
+        players
+    };
+
+    let desc = if players.is_empty() {
+        "```No players are whitelisted.```".to_string()
+    } else {
+        format!("```{}```", players.join(", "))
+    };
+    let embed = embed(&ctx)
+        .await?
+        .title(format!("Whitelisted players ({})", players.len()))
+        .description(desc);
+    ctx.send(CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+/// Compares the whitelist with who has the member role, applying the changes if asked
+#[command(slash_command, prefix_command)]
+async fn sync(
+    ctx: Context<'_>,
+    #[description = "Apply the changes instead of only reporting them"] apply: Option<bool>,
+) -> Result<(), Error> {
+    let apply = apply.unwrap_or(false);
+    let remove = {
+        let data = ctx.serenity_context().data.read().await;
+        let config = data
+            .get::<Config>()
+            .expect("Config not found in context data");
+        config.whitelist_sync.as_ref().map(|opts| opts.remove)
+    };
+    let Some(remove) = remove else {
+        let embed = embed(&ctx)
+            .await?
+            .title("Whitelist sync")
+            .description("Whitelist sync is not configured");
+        ctx.send(CreateReply::default().embed(embed)).await?;
+        return Ok(());
+    };
+    ctx.defer().await?;
+
+    let serenity_ctx = ctx.serenity_context();
+    let plan = whitelist_sync::plan(serenity_ctx).await?;
+    let failures = if apply {
+        whitelist_sync::apply(serenity_ctx, &plan, remove).await
+    } else {
+        Vec::new()
+    };
+    let title = if apply {
+        "Whitelist sync"
+    } else {
+        "Whitelist sync (dry run)"
+    };
+    let mut embed = whitelist_sync::report(embed(&ctx).await?.title(title), &plan, apply, remove);
+    if !failures.is_empty() {
+        embed = embed.field("Failed", whitelist_sync::truncated_list(&failures), false);
+    }
+    ctx.send(CreateReply::default().embed(embed.clone()))
+        .await?;
+    if apply {
+        let author = ctx.author();
+        audit(
+            ctx,
+            embed.description(format!("By <@{}> ({})", author.id, author.name)),
+        )
+        .await;
+    }
+    Ok(())
+}
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use poise::serenity_prelude::prelude::TypeMapKey;
 use serde::Deserialize;
 
@@ -7,6 +9,137 @@ pub struct World {
     pub path: String,
 }
 
+/// Which server events a bridge channel shows
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct BridgeEvents {
+    pub join: bool,
+    pub leave: bool,
+    pub death: bool,
+    pub advancement: bool,
+}
+
+impl Default for BridgeEvents {
+    fn default() -> Self {
+        Self {
+            join: true,
+            leave: true,
+            death: true,
+            advancement: true,
+        }
+    }
+}
+
+/// A Discord channel bridged to a single Taurus server
+#[derive(Debug, Clone, Deserialize)]
+pub struct BridgeChannel {
+    pub server: String,
+    pub channel: u64,
+    #[serde(default)]
+    pub events: BridgeEvents,
+}
+
+/// Posts Minecraft chat through a webhook under each player's name
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookOpts {
+    /// Name of the webhook created or reused in each bridge channel
+    #[serde(default = "default_webhook_name")]
+    pub name: String,
+    /// Avatar URL for the player, `{player}` is replaced with their name
+    pub avatar_url: String,
+}
+
+fn default_webhook_name() -> String {
+    "Taurus Bridge".to_string()
+}
+
+/// Keeps the whitelist in line with who has the member role
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WhitelistSyncOpts {
+    /// Guild whose members are checked for the member role
+    pub guild: u64,
+    /// Seconds between sync runs
+    #[serde(default = "default_sync_interval")]
+    pub interval: u64,
+    /// Remove players whose owner lost the role, instead of only flagging them
+    #[serde(default)]
+    pub remove: bool,
+}
+
+fn default_sync_interval() -> u64 {
+    3600
+}
+
+/// Streams a server's console into a staff-only channel, where staff can
+/// type console commands back
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConsoleOpts {
+    pub channel: u64,
+    /// Taurus session that commands typed in the channel are run on
+    pub server: String,
+    /// Seconds console lines are collected before they are posted
+    #[serde(default = "default_console_interval")]
+    pub interval: u64,
+    /// Messages posted per interval at most, the remaining lines are skipped
+    #[serde(default = "default_console_burst")]
+    pub burst: usize,
+    /// Regular expressions for lines that are never posted
+    #[serde(default)]
+    pub filters: Vec<String>,
+}
+
+fn default_console_interval() -> u64 {
+    2
+}
+
+fn default_console_burst() -> usize {
+    3
+}
+
+/// Settings of one in-game command, keyed by its name
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct IngameCommandOpts {
+    /// Show the output to everyone on the server, not just the player who ran it
+    pub broadcast: bool,
+    /// Players allowed to run the command, everyone when unset
+    pub players: Option<Vec<String>>,
+    /// Discord role the player's linked account needs
+    pub role: Option<u64>,
+    /// Servers the command is enabled on, all of them when unset
+    pub servers: Option<Vec<String>>,
+    /// Seconds a player has to wait between two uses
+    pub cooldown: u64,
+}
+
+/// Shows server status in the bot's activity and the bridge channel topic.
+/// Templates may use `{players}`, `{names}` and `{tps}`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct PresenceOpts {
+    /// Seconds between updates
+    pub interval: u64,
+    pub activity: String,
+    /// Activity shown while Taurus can't be reached
+    pub offline: String,
+    /// Topic for the `chat_bridge` channel, left alone when unset
+    pub topic: Option<String>,
+}
+
+impl Default for PresenceOpts {
+    fn default() -> Self {
+        Self {
+            interval: 60,
+            activity: "{players} players online".to_string(),
+            offline: "Server offline".to_string(),
+            topic: None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct EmbedOpts {
@@ -15,6 +148,39 @@ pub struct EmbedOpts {
     pub footer_icon_url: String,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct TaurusOpts {
+    /// Seconds to wait for the response to a Taurus request
+    pub timeout: u64,
+    /// Seconds before the first reconnection attempt, doubled on each failure
+    pub reconnect_min: u64,
+    /// Upper bound in seconds for the reconnection delay
+    pub reconnect_max: u64,
+    /// Seconds between heartbeat pings
+    pub heartbeat: u64,
+    /// Bridged messages kept while Taurus is unreachable
+    pub queue_size: usize,
+    /// Seconds a bridged message may wait in the queue before it is dropped
+    pub queue_max_age: u64,
+    /// Bridged messages remembered so their edits and deletions can follow
+    pub history_size: usize,
+}
+
+impl Default for TaurusOpts {
+    fn default() -> Self {
+        Self {
+            timeout: 5,
+            reconnect_min: 1,
+            reconnect_max: 300,
+            heartbeat: 30,
+            queue_size: 50,
+            queue_max_age: 300,
+            history_size: 200,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ConfigValue {
@@ -25,9 +191,38 @@ pub struct ConfigValue {
     pub member_role: u64,
     pub grinder_role: u64,
     pub worlds: Vec<World>,
-    pub chat_bridge: u64,
+    /// Combined bridge channel carrying chat from every server
+    #[serde(default)]
+    pub chat_bridge: Option<u64>,
+    /// Server events shown in the combined channel
+    #[serde(default)]
+    pub chat_bridge_events: BridgeEvents,
+    #[serde(default)]
+    pub bridges: Vec<BridgeChannel>,
+    /// Post chat as the players instead of the bot when set
+    #[serde(default)]
+    pub webhook: Option<WebhookOpts>,
     pub info_channel: u64,
+    /// Channel for operational notices such as Taurus authentication failures
+    #[serde(default)]
+    pub staff_channel: Option<u64>,
+    /// Channel logging every staff console command, defaults to the staff channel
+    #[serde(default)]
+    pub audit_channel: Option<u64>,
+    /// Console commands `/execute` and the console channel refuse to run
+    #[serde(default)]
+    pub rcon_denylist: Vec<String>,
+    #[serde(default)]
+    pub console: Option<ConsoleOpts>,
+    #[serde(default)]
+    pub ingame_commands: HashMap<String, IngameCommandOpts>,
+    #[serde(default)]
+    pub whitelist_sync: Option<WhitelistSyncOpts>,
+    #[serde(default)]
+    pub presence: PresenceOpts,
     pub embed_opts: EmbedOpts,
+    #[serde(default)]
+    pub taurus: TaurusOpts,
 }
 
 impl ConfigValue {
@@ -0,0 +1,130 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::{read_to_string, write},
+    io::ErrorKind,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use poise::serenity_prelude::{UserId, prelude::TypeMapKey};
+
+/// How long a link code stays valid
+pub const CODE_LIFETIME: Duration = Duration::from_secs(600);
+/// Characters used in link codes, leaving out look-alikes such as `0` and `O`
+const CODE_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+const CODE_LENGTH: usize = 6;
+
+struct PendingLink {
+    user: UserId,
+    issued: Instant,
+}
+
+/// Discord accounts linked to Minecraft players, persisted as JSON
+pub struct LinkStore {
+    /// Minecraft name by Discord user id
+    links: BTreeMap<u64, String>,
+    /// Codes waiting to be typed in-game
+    pending: HashMap<String, PendingLink>,
+    path: PathBuf,
+}
+
+impl LinkStore {
+    /// Loads the links from `path`, starting empty if the file does not exist
+    pub fn load(path: PathBuf) -> Result<Self, String> {
+        let links = match read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json)
+                .map_err(|e| format!("Failed to parse links file: {}", e))?,
+            Err(e) if e.kind() == ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(format!("Failed to read links file: {}", e)),
+        };
+        Ok(Self {
+            links,
+            pending: HashMap::new(),
+            path,
+        })
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(&self.links)
+            .map_err(|e| format!("Failed to serialise links: {}", e))?;
+        write(&self.path, json).map_err(|e| format!("Failed to write links file: {}", e))
+    }
+
+    /// Issues a new code for `user`, replacing any code they had before
+    pub fn issue_code(&mut self, user: UserId) -> String {
+        self.pending
+            .retain(|_, pending| pending.user != user && pending.issued.elapsed() < CODE_LIFETIME);
+        loop {
+            let code = (0..CODE_LENGTH)
+                .map(|_| CODE_ALPHABET[fastrand::usize(..CODE_ALPHABET.len())] as char)
+                .collect::<String>();
+            if !self.pending.contains_key(&code) {
+                let pending = PendingLink {
+                    user,
+                    issued: Instant::now(),
+                };
+                self.pending.insert(code.clone(), pending);
+                return code;
+            }
+        }
+    }
+
+    /// Whether `code` was issued and not yet typed in-game
+    pub fn is_pending(&self, code: &str) -> bool {
+        self.pending.contains_key(&code.trim().to_uppercase())
+    }
+
+    /// Links `player` to whoever was issued `code`, if it is still valid
+    pub fn confirm(&mut self, code: &str, player: &str) -> Result<Option<UserId>, String> {
+        let code = code.trim().to_uppercase();
+        let Some(pending) = self.pending.remove(&code) else {
+            return Ok(None);
+        };
+        if pending.issued.elapsed() >= CODE_LIFETIME {
+            return Ok(None);
+        }
+        self.set(pending.user, player)?;
+        Ok(Some(pending.user))
+    }
+
+    /// Links `user` to `player`, unlinking anyone else who had that player
+    pub fn set(&mut self, user: UserId, player: &str) -> Result<(), String> {
+        self.links
+            .retain(|_, linked| !linked.eq_ignore_ascii_case(player));
+        self.links.insert(user.get(), player.to_string());
+        self.save()
+    }
+
+    /// Unlinks `user`, returning the player they were linked to
+    pub fn remove(&mut self, user: UserId) -> Result<Option<String>, String> {
+        let removed = self.links.remove(&user.get());
+        if removed.is_some() {
+            self.save()?;
+        }
+        Ok(removed)
+    }
+
+    pub fn player(&self, user: UserId) -> Option<&str> {
+        self.links.get(&user.get()).map(String::as_str)
+    }
+
+    pub fn user(&self, player: &str) -> Option<UserId> {
+        self.links
+            .iter()
+            .find(|(_, linked)| linked.eq_ignore_ascii_case(player))
+            .map(|(user, _)| UserId::new(*user))
+    }
+
+    /// Every linked Discord user and their Minecraft name
+    pub fn iter(&self) -> impl Iterator<Item = (UserId, &str)> {
+        self.links
+            .iter()
+            .map(|(user, player)| (UserId::new(*user), player.as_str()))
+    }
+}
+
+pub struct Links;
+
+impl TypeMapKey for Links {
+    type Value = LinkStore;
+}
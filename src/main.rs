@@ -1,41 +1,40 @@
 pub mod anvil;
+pub mod backup_schedule;
+pub mod calculator;
 pub mod commands;
 pub mod config;
+pub mod links;
+pub mod presence;
 pub mod scoreboard;
 pub mod taurus;
+pub mod whitelist_sync;
 
 use std::collections::{BTreeMap, HashMap};
-use std::collections::hash_map::Entry;
 use std::env;
-use std::fs::{File, read_to_string};
-use std::io::{BufReader, Read, Write};
+use std::fs::read_to_string;
 use std::path::PathBuf;
-use std::sync::{Arc, Once};
+use std::sync::Once;
+use std::time::Duration;
 
-use flate2::bufread::GzDecoder;
-use futures::lock::Mutex;
-use http::Uri;
-use poise::Prefix;
 use poise::samples::create_application_commands;
-use poise::serenity_prelude::futures::{SinkExt, StreamExt};
 use poise::serenity_prelude::prelude::TypeMapKey;
 use poise::serenity_prelude::{
-    ChannelId, Client, Command, Context, EventHandler, GatewayIntents, Message, Ready, async_trait,
+    ChannelId, Client, Command, Context, EventHandler, GatewayIntents, GuildId, Message, MessageId,
+    MessageUpdateEvent, ReactionType, Ready, async_trait,
 };
-use resvg::usvg::roxmltree::Namespace;
-use serde::{Deserialize, Serialize};
-use tokio::net::TcpStream;
-use tokio::sync::mpsc::{Receiver, Sender};
-use tokio_websockets::{
-    ClientBuilder, Error, MaybeTlsStream, Message as WSMessage, WebSocketStream,
-};
-use valence_nbt::{Value, from_binary};
 
 use crate::anvil::run_anvil;
-use crate::commands::{member, public};
+use crate::backup_schedule::{ScheduleStore, Schedules, run_backup_scheduler};
+use crate::commands::{ingame::Cooldowns, member, public, staff};
 use crate::config::{Config, ConfigValue};
+use crate::links::{LinkStore, Links};
+use crate::presence::run_presence;
 use crate::scoreboard::{CachedScoreboard, Scoreboards};
-use crate::taurus::{TaurusChannel, send_message, taurus_connection};
+use crate::taurus::{
+    BridgeHistory, BridgeTarget, BridgedMessage, History, Taurus, TaurusClient, console_input,
+    send_edit, send_message, send_removal, taurus_client, taurus_connection,
+};
+use crate::whitelist_sync::run_whitelist_sync;
 
 #[derive(Debug)]
 struct Handler;
@@ -47,22 +46,88 @@ impl EventHandler for Handler {
         if msg.author.bot {
             return; // Ignore messages from bots
         }
-        {
+        if console_input(&ctx, &msg).await {
+            return;
+        }
+        let (channel, id) = (msg.channel_id, msg.id);
+        let author = msg.author.name.clone();
+        let target = {
             let data = ctx.data.read().await;
-            let id = data
-                .get::<Config>()
-                .expect("TaurusChannel not found")
-                .chat_bridge;
+            let config = data.get::<Config>().expect("Config not found");
 
-            if msg.channel_id != ChannelId::new(id) {
-                return; // Ignore messages not in the chat bridge channel
-            }
+            let Some(target) = BridgeTarget::for_channel(config, msg.channel_id) else {
+                return; // Ignore messages not in a chat bridge channel
+            };
+            target
+        };
+        let taurus = taurus_client(&ctx).await;
+        // Not holding the data lock, the connection task may need it to make
+        // room in the channel
+        if let Err(e) = send_message(&ctx.cache, msg, &target, &taurus).await {
+            println!("ERROR: Failed to bridge message to Taurus: {}", e);
+            let reaction = ReactionType::Unicode("❌".to_string());
+            channel.create_reaction(&ctx.http, id, reaction).await.ok();
+            return;
+        }
+        let mut data = ctx.data.write().await;
+        data.get_mut::<History>()
+            .expect("History not found")
+            .record(id, BridgedMessage { target, author });
+    }
 
-            let (tx, _rx) = data
-                .get::<TaurusChannel>()
-                .expect("TaurusChannel not found");
+    async fn message_update(
+        &self,
+        ctx: Context,
+        _old: Option<Message>,
+        new: Option<Message>,
+        event: MessageUpdateEvent,
+    ) {
+        // Embeds being unfurled also update a message, only follow real edits
+        if event.edited_timestamp.is_none() || event.content.is_none() {
+            return;
+        }
+        let bridged = {
+            let data = ctx.data.read().await;
+            let history = data.get::<History>().expect("History not found");
+            let Some(bridged) = history.get(event.id).cloned() else {
+                return; // Ignore messages that were never bridged
+            };
+            bridged
+        };
+        let taurus = taurus_client(&ctx).await;
+        let msg = match new {
+            Some(msg) => msg,
+            None => match event.channel_id.message(&ctx.http, event.id).await {
+                Ok(msg) => msg,
+                Err(e) => {
+                    println!("ERROR: Failed to fetch edited message: {}", e);
+                    return;
+                }
+            },
+        };
+        if let Err(e) = send_edit(&ctx.cache, &msg, &bridged.target, &taurus).await {
+            println!("ERROR: Failed to bridge message edit to Taurus: {}", e);
+        }
+    }
 
-            send_message(msg, tx).await;
+    async fn message_delete(
+        &self,
+        ctx: Context,
+        _channel: ChannelId,
+        id: MessageId,
+        _guild: Option<GuildId>,
+    ) {
+        let bridged = {
+            let mut data = ctx.data.write().await;
+            let history = data.get_mut::<History>().expect("History not found");
+            let Some(bridged) = history.remove(id) else {
+                return; // Ignore messages that were never bridged
+            };
+            bridged
+        };
+        let taurus = taurus_client(&ctx).await;
+        if let Err(e) = send_removal(&bridged.author, &bridged.target, &taurus).await {
+            println!("ERROR: Failed to bridge message deletion to Taurus: {}", e);
         }
     }
 
@@ -70,21 +135,34 @@ impl EventHandler for Handler {
         static START_CHILD_THREADS: Once = Once::new();
         let mut data = ctx.data.write().await;
         START_CHILD_THREADS.call_once(|| {
-            let (tx, rx) = tokio::sync::mpsc::channel(100);
-            let command_responses = Arc::new(Mutex::new(Vec::new()));
-            {
-                // Create a channel for Taurus messages
-
-                data.insert::<TaurusChannel>((tx, command_responses.clone()));
-            }
+            let timeout = data
+                .get::<Config>()
+                .expect("Config not found")
+                .taurus
+                .timeout;
+            let url = env::var("TAURUS_URL").expect("Expected a TAURUS_URL environment variable");
+            let (client, rx) = TaurusClient::new(url, Duration::from_secs(timeout));
+            data.insert::<Taurus>(client.clone());
             let taurus_ctx = ctx.clone();
             tokio::spawn(async move {
-                taurus_connection(&taurus_ctx, rx, command_responses).await;
+                taurus_connection(&taurus_ctx, rx, client).await;
             });
             let anvil_ctx = ctx.clone();
             tokio::spawn(async move {
                 run_anvil(&anvil_ctx).await;
             });
+            let presence_ctx = ctx.clone();
+            tokio::spawn(async move {
+                run_presence(&presence_ctx).await;
+            });
+            let scheduler_ctx = ctx.clone();
+            tokio::spawn(async move {
+                run_backup_scheduler(&scheduler_ctx).await;
+            });
+            let sync_ctx = ctx.clone();
+            tokio::spawn(async move {
+                run_whitelist_sync(&sync_ctx).await;
+            });
             println!("INFO: Started child threads");
         });
         println!("INFO: {} is connected!", ready.user.name);
@@ -112,7 +190,8 @@ async fn main() {
     // Login with a bot token from the environment
     let token = env::var("API_TOKEN").expect("Expected a token in the environment");
     // Set gateway intents, which decides what events the bot will be notified about
-    let intents = GatewayIntents::GUILD_MESSAGES
+    let intents = GatewayIntents::GUILDS
+        | GatewayIntents::GUILD_MESSAGES
         | GatewayIntents::DIRECT_MESSAGES
         | GatewayIntents::MESSAGE_CONTENT;
 
@@ -124,11 +203,21 @@ async fn main() {
                 public::score(),
                 public::iscore(),
                 public::list(),
+                public::tps(),
+                public::worldsize(),
                 public::invite(),
+                public::link(),
+                public::unlink(),
+                public::whois(),
                 member::backup(),
                 member::grinder(),
                 member::session(),
                 member::reconnect(),
+                member::taurus(),
+                staff::execute(),
+                staff::links(),
+                staff::schedule(),
+                staff::whitelist(),
             ],
             prefix_options: poise::PrefixFrameworkOptions {
                 prefix: config.prefix.first().cloned(),
@@ -168,12 +257,20 @@ async fn main() {
                 .get_world_path("SMP")
                 .expect("Failed to get world path"),
         );
+        data.insert::<History>(BridgeHistory::new(config.taurus.history_size));
+        let links = LinkStore::load(PathBuf::from("data/links.json"))
+            .expect("Failed to load account links");
+        data.insert::<Links>(links);
+        let schedules = ScheduleStore::load(PathBuf::from("data/backup_schedules.json"))
+            .expect("Failed to load backup schedules");
+        data.insert::<Schedules>(schedules);
         // Insert the chat bridge URL into the data
         data.insert::<Config>(config);
 
         let cached_scoreboard = CachedScoreboard::new(scoreboard_path);
         data.insert::<Scoreboards>(cached_scoreboard);
         data.insert::<EvalRepl>(HashMap::new());
+        data.insert::<Cooldowns>(HashMap::new());
     }
 
     println!("INFO: Connecting to Discord...");
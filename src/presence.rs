@@ -0,0 +1,92 @@
+use std::{
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use poise::serenity_prelude::{ActivityData, ChannelId, Context, EditChannel};
+use tokio::time::sleep;
+
+use crate::{commands::public::world_tps, config::Config, taurus::taurus_client};
+
+/// Discord only allows two topic changes per channel every ten minutes
+const TOPIC_COOLDOWN: Duration = Duration::from_secs(300);
+
+/// Fills in `{players}`, `{names}` and `{tps}`
+fn render(template: &str, players: &[String], tps: &str) -> String {
+    let names = if players.is_empty() {
+        "nobody".to_string()
+    } else {
+        players.join(", ")
+    };
+    template
+        .replace("{players}", &players.len().to_string())
+        .replace("{names}", &names)
+        .replace("{tps}", tps)
+}
+
+/// Periodically shows the player count and TPS in the bot's activity and,
+/// if configured, the bridge channel topic
+pub async fn run_presence(ctx: &Context) {
+    let (opts, worlds, channel) = {
+        let data = ctx.data.read().await;
+        let config = data.get::<Config>().expect("Config not found");
+        (
+            config.presence.clone(),
+            config.worlds.clone(),
+            config.chat_bridge.map(ChannelId::new),
+        )
+    };
+    let taurus = taurus_client(ctx).await;
+    let interval = Duration::from_secs(opts.interval.max(15));
+    let mut topic = None;
+    let mut topic_changed: Option<Instant> = None;
+    loop {
+        let tps = worlds
+            .iter()
+            .filter_map(|world| match world_tps(Path::new(&world.path)) {
+                Ok(tps) => tps.map(|tps| format!("{} {:.1}", world.name, tps)),
+                Err(e) => {
+                    println!("ERROR: Failed to read TPS of {}: {}", world.name, e);
+                    None
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        let tps = if tps.is_empty() {
+            "unknown".to_string()
+        } else {
+            tps
+        };
+
+        let players = match taurus.list().await {
+            Ok(servers) => Some(
+                servers
+                    .into_iter()
+                    .flat_map(|server| server.players)
+                    .collect::<Vec<_>>(),
+            ),
+            Err(_) => None,
+        };
+        let activity = match &players {
+            Some(players) => render(&opts.activity, players, &tps),
+            None => opts.offline.clone(),
+        };
+        ctx.set_activity(Some(ActivityData::custom(activity)));
+
+        if let (Some(template), Some(channel), Some(players)) = (&opts.topic, channel, &players) {
+            let new_topic = render(template, players, &tps);
+            let cooled_down = topic_changed.is_none_or(|at| at.elapsed() >= TOPIC_COOLDOWN);
+            if topic.as_ref() != Some(&new_topic) && cooled_down {
+                let edit = EditChannel::new().topic(&new_topic);
+                match channel.edit(&ctx.http, edit).await {
+                    Ok(_) => {
+                        topic = Some(new_topic);
+                        topic_changed = Some(Instant::now());
+                    }
+                    Err(e) => println!("ERROR: Failed to update bridge channel topic: {}", e),
+                }
+            }
+        }
+        sleep(interval).await;
+    }
+}
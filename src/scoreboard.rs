@@ -26,6 +26,12 @@ struct WhitelistName {
     name: String,
 }
 
+impl Default for ScoreboardNames {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl ScoreboardNames {
     pub fn new() -> Self {
         Self {
@@ -106,7 +112,7 @@ impl CachedScoreboard {
 
     pub fn load_names(&mut self) -> Result<(), String> {
         let mut file =
-            File::open(&self.path()).map_err(|e| format!("Failed to open scoreboard file: {}", e))?;
+            File::open(self.path()).map_err(|e| format!("Failed to open scoreboard file: {}", e))?;
         let mut buf = Vec::new();
         let mut d = GzDecoder::new(BufReader::new(&mut file));
         d.read_to_end(&mut buf)
@@ -148,27 +154,31 @@ impl CachedScoreboard {
             })
             .collect::<Vec<ScoreboardName>>();
 
+        self.load_whitelist()?;
+
+        self.scoreboard_names.update(names);
+
+        Ok(())
+    }
 
+    /// Rereads `whitelist.json`, e.g. after the whitelist was changed over RCON
+    pub fn load_whitelist(&mut self) -> Result<(), String> {
         let whitelist_string = read_to_string(self.whitelist_path())
             .map_err(|e| format!("Failed to read whitelist file: {}", e))?;
         let whitelist: Vec<WhitelistName> = serde_json::from_str(&whitelist_string)
             .map_err(|e| format!("Failed to parse whitelist file: {}", e))?;
 
-        let whitelist = whitelist
+        self.whitelist = whitelist
             .into_iter()
             .map(|player| player.name)
             .collect::<HashSet<String>>();
 
-        self.whitelist = whitelist;
-
-        self.scoreboard_names.update(names);
-
         Ok(())
     }
 
     pub fn load_scoreboard(&mut self, name: &str) -> Result<(), String> {
         let mut file =
-            File::open(&self.path()).map_err(|e| format!("Failed to open scoreboard file: {}", e))?;
+            File::open(self.path()).map_err(|e| format!("Failed to open scoreboard file: {}", e))?;
         let mut buf = Vec::new();
         let mut d = GzDecoder::new(BufReader::new(&mut file));
         d.read_to_end(&mut buf)
@@ -187,23 +197,21 @@ impl CachedScoreboard {
         let mut scores = Vec::new();
         let mut total = 0;
         for score in player_scores.iter() {
-            if let Value::Compound(compound) = score.to_value() {
-                if let Some(Value::String(objective_name)) = compound.get("Objective") {
-                    if objective_name == name {
-                        if let Some(Value::String(player_name)) = compound.get("Name") {
-                            if player_name == "Total" {
-                                continue;
-                            }
-                            if let Some(Value::Int(score_value)) = compound.get("Score") {
-                                scores.push((player_name.to_string(), *score_value));
-                                total += *score_value as i64;
-                            }
-                        }
-                    }
+            if let Value::Compound(compound) = score.to_value()
+                && let Some(Value::String(objective_name)) = compound.get("Objective")
+                && objective_name == name
+                && let Some(Value::String(player_name)) = compound.get("Name")
+            {
+                if player_name == "Total" {
+                    continue;
+                }
+                if let Some(Value::Int(score_value)) = compound.get("Score") {
+                    scores.push((player_name.to_string(), *score_value));
+                    total += *score_value as i64;
                 }
             }
         }
-        scores.sort_by(|a, b| b.1.cmp(&a.1));
+        scores.sort_by_key(|score| std::cmp::Reverse(score.1));
 
         if scores.is_empty() {
             return Err(format!("No scores found for objective '{}'", name));
@@ -223,7 +231,7 @@ impl CachedScoreboard {
     }
 
     pub fn get_scoreboard(&mut self, name: &str) -> Result<&Scoreboard, String> {
-        if self.scoreboards.get(name).is_none() {
+        if !self.scoreboards.contains_key(name) {
             self.load_scoreboard(name)?;
         }
         self.scoreboards
@@ -0,0 +1,35 @@
+use std::time::Duration;
+
+/// Exponential delay between reconnection attempts
+pub struct Backoff {
+    min: Duration,
+    max: Duration,
+    attempt: u32,
+}
+
+impl Backoff {
+    pub fn new(min: Duration, max: Duration) -> Self {
+        // A zero delay would never back off at all
+        let min = min.max(Duration::from_secs(1));
+        Self {
+            min,
+            max: max.max(min),
+            attempt: 0,
+        }
+    }
+
+    pub fn attempt(&self) -> u32 {
+        self.attempt
+    }
+
+    /// Returns the delay before the next attempt, doubling it each time
+    pub fn next_delay(&mut self) -> Duration {
+        let factor = 2u32.saturating_pow(self.attempt.min(16));
+        self.attempt += 1;
+        self.min.saturating_mul(factor).min(self.max)
+    }
+
+    pub fn reset(&mut self) {
+        self.attempt = 0;
+    }
+}
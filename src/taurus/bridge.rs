@@ -0,0 +1,376 @@
+use std::collections::HashMap;
+
+use futures::lock::Mutex;
+use poise::serenity_prelude::{
+    Cache, ChannelId, Colour, Context, CreateAllowedMentions, CreateEmbed, CreateEmbedAuthor,
+    CreateEmbedFooter, CreateMessage, CreateWebhook, Error as SerenityError, ExecuteWebhook,
+    Message, Webhook,
+};
+
+use crate::config::{BridgeEvents, ConfigValue, WebhookOpts};
+
+use super::{
+    TaurusClient, TaurusError,
+    component::{ClickEvent, TextComponent},
+    format::{
+        DISCORD_MESSAGE_LIMIT, MC_CHAT_LIMIT, discord_to_minecraft, escape_markdown,
+        minecraft_to_discord, split_chat, split_message,
+    },
+    mc_format,
+    protocol::{ChatLine, EventKind, Outbound, ServerEvent},
+    queue::BridgeMessage,
+};
+
+/// Where a Discord message posted in a bridge channel should go
+#[derive(Debug, Clone)]
+pub enum BridgeTarget {
+    /// The combined channel, broadcast to every server
+    All,
+    /// A channel bridged to a single Taurus server
+    Server(String),
+}
+
+impl BridgeTarget {
+    pub fn for_channel(config: &ConfigValue, channel: ChannelId) -> Option<Self> {
+        if config.chat_bridge == Some(channel.get()) {
+            return Some(BridgeTarget::All);
+        }
+        config
+            .bridges
+            .iter()
+            .find(|bridge| bridge.channel == channel.get())
+            .map(|bridge| BridgeTarget::Server(bridge.server.clone()))
+    }
+}
+
+/// Routing of Minecraft chat to the Discord bridge channels
+pub(super) struct Bridge {
+    combined: Option<ChannelId>,
+    servers: HashMap<String, ChannelId>,
+    pub(super) cmd_prefix: Vec<String>,
+    /// Events shown in each channel
+    events: HashMap<ChannelId, BridgeEvents>,
+    webhook: Option<WebhookOpts>,
+    /// Webhooks already looked up or created, by channel
+    webhooks: Mutex<HashMap<ChannelId, Webhook>>,
+}
+
+impl Bridge {
+    pub(super) fn from_config(config: &ConfigValue) -> Self {
+        Self {
+            combined: config.chat_bridge.map(ChannelId::new),
+            servers: config
+                .bridges
+                .iter()
+                .map(|bridge| (bridge.server.clone(), ChannelId::new(bridge.channel)))
+                .collect(),
+            cmd_prefix: config.prefix.clone(),
+            events: config
+                .bridges
+                .iter()
+                .map(|bridge| (ChannelId::new(bridge.channel), bridge.events.clone()))
+                .chain(
+                    config.chat_bridge.map(|channel| {
+                        (ChannelId::new(channel), config.chat_bridge_events.clone())
+                    }),
+                )
+                .collect(),
+            webhook: config.webhook.clone(),
+            webhooks: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Sends text from Minecraft, split to fit Discord and without pinging anyone
+    async fn say(ctx: &Context, channel: ChannelId, content: &str) {
+        for part in split_message(content, DISCORD_MESSAGE_LIMIT) {
+            let message = CreateMessage::new()
+                .content(part)
+                .allowed_mentions(CreateAllowedMentions::new());
+            if let Err(e) = channel.send_message(&ctx.http, message).await {
+                println!("ERROR: Failed to send message to Discord: {}", e);
+            }
+        }
+    }
+
+    /// Finds the bridge webhook in a channel, creating it if there is none
+    async fn find_webhook(
+        &self,
+        ctx: &Context,
+        channel: ChannelId,
+        name: &str,
+    ) -> Result<Webhook, SerenityError> {
+        let mut webhooks = self.webhooks.lock().await;
+        if let Some(webhook) = webhooks.get(&channel) {
+            return Ok(webhook.clone());
+        }
+        let existing = channel
+            .webhooks(&ctx.http)
+            .await?
+            .into_iter()
+            .find(|webhook| webhook.name.as_deref() == Some(name) && webhook.token.is_some());
+        let webhook = match existing {
+            Some(webhook) => webhook,
+            None => {
+                println!("INFO: Creating bridge webhook in channel {}", channel);
+                channel
+                    .create_webhook(&ctx.http, CreateWebhook::new(name))
+                    .await?
+            }
+        };
+        webhooks.insert(channel, webhook.clone());
+        Ok(webhook)
+    }
+
+    /// Posts a player's chat through the channel's webhook
+    async fn say_as(
+        &self,
+        ctx: &Context,
+        channel: ChannelId,
+        opts: &WebhookOpts,
+        player: &str,
+        username: &str,
+        content: &str,
+    ) -> Result<(), SerenityError> {
+        let webhook = self.find_webhook(ctx, channel, &opts.name).await?;
+        let avatar_url = opts.avatar_url.replace("{player}", player);
+        for part in split_message(content, DISCORD_MESSAGE_LIMIT) {
+            let message = ExecuteWebhook::new()
+                .content(part)
+                .username(username)
+                .avatar_url(&avatar_url)
+                .allowed_mentions(CreateAllowedMentions::new());
+            webhook.execute(&ctx.http, false, message).await?;
+        }
+        Ok(())
+    }
+
+    /// Channels a line from `server` is posted to, and whether each needs the
+    /// server tag. Lines go to the server's own channel and the combined
+    /// channel; lines without a known server go to the combined channel, or
+    /// to every server channel if there is none.
+    fn targets(&self, server: Option<&str>) -> Vec<(ChannelId, bool)> {
+        let mut targets = Vec::new();
+        match server.and_then(|server| self.servers.get(server)) {
+            Some(channel) => targets.push((*channel, false)),
+            None if self.combined.is_none() => {
+                targets.extend(self.servers.values().map(|channel| (*channel, true)));
+            }
+            None => {}
+        }
+        targets.extend(self.combined.map(|channel| (channel, true)));
+        targets
+    }
+
+    pub(super) async fn relay_chat(&self, ctx: &Context, chat: &ChatLine) {
+        let text = minecraft_to_discord(&chat.text);
+        let untagged = format!("<{}> {}", escape_markdown(&chat.username), text);
+        let tagged = format!("[{}] {}", escape_markdown(&chat.server), untagged);
+        for (channel, needs_tag) in self.targets(Some(&chat.server)) {
+            let fallback = if needs_tag { &tagged } else { &untagged };
+            let Some(opts) = &self.webhook else {
+                Self::say(ctx, channel, fallback).await;
+                continue;
+            };
+            let username = if needs_tag {
+                format!("{} [{}]", chat.username, chat.server)
+            } else {
+                chat.username.clone()
+            };
+            let posted = self
+                .say_as(ctx, channel, opts, &chat.username, &username, &text)
+                .await;
+            if let Err(e) = posted {
+                println!("ERROR: Failed to post chat through webhook: {}", e);
+                self.webhooks.lock().await.remove(&channel);
+                Self::say(ctx, channel, fallback).await;
+            }
+        }
+    }
+
+    /// Whether a channel shows this kind of event
+    fn shows(&self, channel: ChannelId, kind: EventKind) -> bool {
+        let Some(events) = self.events.get(&channel) else {
+            return true;
+        };
+        match kind {
+            EventKind::Join => events.join,
+            EventKind::Leave => events.leave,
+            EventKind::Death => events.death,
+            EventKind::Advancement => events.advancement,
+        }
+    }
+
+    pub(super) async fn relay_event(&self, ctx: &Context, event: &ServerEvent) {
+        let (colour, icon) = match event.kind {
+            EventKind::Join => (Colour::DARK_GREEN, "📥"),
+            EventKind::Leave => (Colour::RED, "📤"),
+            EventKind::Death => (Colour::DARK_GREY, "💀"),
+            EventKind::Advancement => (Colour::GOLD, "🏆"),
+        };
+        let mut author = CreateEmbedAuthor::new(&event.player);
+        if let Some(opts) = &self.webhook {
+            author = author.icon_url(opts.avatar_url.replace("{player}", &event.player));
+        }
+        let embed = CreateEmbed::new()
+            .author(author)
+            .colour(colour)
+            .description(format!("{} {}", icon, escape_markdown(&event.message)));
+        for (channel, needs_tag) in self.targets(event.server.as_deref()) {
+            if !self.shows(channel, event.kind) {
+                continue;
+            }
+            let mut embed = embed.clone();
+            if needs_tag && let Some(server) = &event.server {
+                embed = embed.footer(CreateEmbedFooter::new(server));
+            }
+            let message = CreateMessage::new().embed(embed);
+            if let Err(e) = channel.send_message(&ctx.http, message).await {
+                println!("ERROR: Failed to send event to Discord: {}", e);
+            }
+        }
+    }
+
+    pub(super) async fn relay_msg(&self, ctx: &Context, body: &str) {
+        let body = minecraft_to_discord(body);
+        let server = body
+            .strip_prefix('[')
+            .and_then(|rest| rest.split_once(']'))
+            .map(|(server, rest)| (server, rest.trim_start()));
+        let (server, untagged) = match server {
+            Some((server, rest)) => (Some(server), rest),
+            None => (None, body.as_str()),
+        };
+        for (channel, needs_tag) in self.targets(server) {
+            let content = if needs_tag { body.as_str() } else { untagged };
+            Self::say(ctx, channel, content).await;
+        }
+    }
+}
+
+/// Rewrites a broadcast frame so it only reaches one server
+fn retarget(frame: Outbound, server: &str) -> Outbound {
+    let component = match frame {
+        Outbound::Msg(text) => TextComponent::new(text),
+        Outbound::Url { url, label } => TextComponent::new(label).click(ClickEvent::OpenUrl(url)),
+        other => return other,
+    };
+    Outbound::Rcon {
+        server: server.to_string(),
+        command: format!("tellraw @a {}", component),
+    }
+}
+
+/// Limits broadcast frames to the target's server
+fn route(frames: Vec<Outbound>, target: &BridgeTarget) -> Vec<Outbound> {
+    match target {
+        BridgeTarget::All => frames,
+        BridgeTarget::Server(server) => frames
+            .into_iter()
+            .map(|frame| retarget(frame, server))
+            .collect(),
+    }
+}
+
+/// Splits translated text into chat lines that fit in Minecraft
+fn push_lines(frames: &mut Vec<Outbound>, text: &str) {
+    for line in text.split('\n') {
+        frames.extend(
+            split_chat(line, MC_CHAT_LIMIT)
+                .into_iter()
+                .map(Outbound::Msg),
+        );
+    }
+}
+
+pub async fn send_message(
+    cache: &Cache,
+    msg: Message,
+    target: &BridgeTarget,
+    taurus: &TaurusClient,
+) -> Result<(), TaurusError> {
+    let origin = Some((msg.channel_id, msg.id));
+    let author_name = msg.author.name.clone();
+    let mut frames = Vec::new();
+    if let Some(reply) = &msg.referenced_message {
+        // Only the first line of the reply is shown
+        let content = discord_to_minecraft(cache, reply);
+        let preview = format!(
+            "reply -> {} {}",
+            mc_format(&reply.author.name, &['d']),
+            mc_format(content.lines().next().unwrap_or_default(), &['o'])
+        );
+        frames.extend(
+            split_chat(&preview, MC_CHAT_LIMIT)
+                .into_iter()
+                .take(1)
+                .map(Outbound::Msg),
+        );
+    }
+    if !msg.content.is_empty() {
+        let line = format!(
+            "[{}] {}",
+            mc_format(&author_name, &['5']),
+            discord_to_minecraft(cache, &msg)
+        );
+        push_lines(&mut frames, &line);
+    }
+
+    let has_attachments = !msg.attachments.is_empty();
+    if has_attachments {
+        let text = if msg.attachments.len() == 1 {
+            "Attachment".to_string()
+        } else {
+            format!("Attachments ({})", msg.attachments.len())
+        };
+        frames.push(Outbound::Msg(format!(
+            "[{}] {}",
+            mc_format(&author_name, &['5']),
+            text
+        )));
+        for attachment in msg.attachments {
+            frames.push(Outbound::Url {
+                url: attachment.url,
+                label: mc_format(&attachment.filename, &['9', 'n']),
+            });
+        }
+    }
+    let frames = route(frames, target);
+    taurus.bridge(BridgeMessage::new(frames, origin)).await
+}
+
+/// Sends the new content of an edited message
+pub async fn send_edit(
+    cache: &Cache,
+    msg: &Message,
+    target: &BridgeTarget,
+    taurus: &TaurusClient,
+) -> Result<(), TaurusError> {
+    let line = format!(
+        "[{}] {} {}",
+        mc_format(&msg.author.name, &['5']),
+        mc_format("(edited)", &['7', 'o']),
+        discord_to_minecraft(cache, msg)
+    );
+    let mut frames = Vec::new();
+    push_lines(&mut frames, &line);
+    let origin = Some((msg.channel_id, msg.id));
+    taurus
+        .bridge(BridgeMessage::new(route(frames, target), origin))
+        .await
+}
+
+/// Tells players that a bridged message was deleted on Discord
+pub async fn send_removal(
+    author: &str,
+    target: &BridgeTarget,
+    taurus: &TaurusClient,
+) -> Result<(), TaurusError> {
+    let frames = vec![Outbound::Msg(format!(
+        "[{}] {}",
+        mc_format(author, &['5']),
+        mc_format("message removed", &['7', 'o'])
+    ))];
+    taurus
+        .bridge(BridgeMessage::new(route(frames, target), None))
+        .await
+}
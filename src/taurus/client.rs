@@ -0,0 +1,422 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant, SystemTime},
+};
+
+use futures::lock::Mutex;
+use poise::serenity_prelude::prelude::TypeMapKey;
+use tokio::sync::{
+    mpsc::{self, Receiver, Sender},
+    oneshot,
+};
+
+use super::{
+    protocol::{
+        BackupEntry, Inbound, Outbound, ProtocolError, ServerPlayers, Session, SessionStatus,
+        encode,
+    },
+    queue::BridgeMessage,
+};
+
+/// What the connection task is currently doing
+#[derive(Debug, Clone)]
+pub enum ConnectionState {
+    Connecting,
+    Connected { since: Instant },
+    Reconnecting { attempt: u32, retry_at: Instant },
+    AuthFailed,
+}
+
+impl fmt::Display for ConnectionState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConnectionState::Connecting => write!(f, "connecting"),
+            ConnectionState::Connected { .. } => write!(f, "connected"),
+            ConnectionState::Reconnecting { attempt, retry_at } => write!(
+                f,
+                "reconnecting (attempt {}, next try in {}s)",
+                attempt,
+                retry_at.saturating_duration_since(Instant::now()).as_secs()
+            ),
+            ConnectionState::AuthFailed => {
+                write!(f, "authentication failed, waiting for a manual reconnect")
+            }
+        }
+    }
+}
+
+/// Outcome of the password handshake on the current connection
+#[derive(Debug, Clone)]
+pub enum AuthState {
+    /// No connection has finished the handshake yet
+    Pending,
+    /// `TAURUS_PASS` is not set, so the connection is unauthenticated
+    Unconfigured,
+    Authenticated,
+    Failed(String),
+}
+
+impl fmt::Display for AuthState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuthState::Pending => write!(f, "pending"),
+            AuthState::Unconfigured => write!(f, "not configured (TAURUS_PASS unset)"),
+            AuthState::Authenticated => write!(f, "authenticated"),
+            AuthState::Failed(reason) => write!(f, "failed: {}", reason),
+        }
+    }
+}
+
+/// Snapshot of the connection reported by `/taurus status`
+#[derive(Debug, Clone)]
+pub struct TaurusStatus {
+    pub url: String,
+    pub state: ConnectionState,
+    pub auth: AuthState,
+    pub last_error: Option<(String, SystemTime)>,
+}
+
+#[derive(Debug)]
+pub enum TaurusError {
+    /// There is no live connection to send the frame over
+    NotConnected(ConnectionState),
+    /// The connection task has stopped and no longer accepts frames
+    Closed,
+    /// The connection dropped before a response arrived
+    Disconnected,
+    /// No response of the expected type arrived in time
+    Timeout(&'static str),
+    /// The response could not be parsed
+    Protocol(ProtocolError),
+    /// The frame could not be built, e.g. a name with spaces
+    Invalid(ProtocolError),
+    /// Taurus answered with a frame of the wrong type
+    Unexpected(&'static str),
+    /// A request was made with a frame Taurus never answers
+    NoResponse(&'static str),
+}
+
+impl fmt::Display for TaurusError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TaurusError::NotConnected(state) => write!(f, "Taurus is not connected: {}", state),
+            TaurusError::Closed => write!(f, "Taurus connection task is not running"),
+            TaurusError::Disconnected => write!(f, "Taurus disconnected before responding"),
+            TaurusError::Timeout(command) => write!(f, "Taurus did not answer {} in time", command),
+            TaurusError::Protocol(e) => write!(f, "Taurus sent a bad response: {}", e),
+            TaurusError::Invalid(e) => write!(f, "Can't send to Taurus: {}", e),
+            TaurusError::Unexpected(command) => {
+                write!(f, "Taurus answered with an unexpected {} frame", command)
+            }
+            TaurusError::NoResponse(command) => write!(f, "Taurus does not answer {}", command),
+        }
+    }
+}
+
+impl std::error::Error for TaurusError {}
+
+/// Instructions for the connection task
+pub enum TaurusCommand {
+    Send(Outbound),
+    /// A frame whose response goes to `tx`
+    Request {
+        frame: Outbound,
+        id: u64,
+        tx: oneshot::Sender<Response>,
+    },
+    /// Chat bridge traffic, buffered while Taurus is unreachable
+    Bridge(BridgeMessage),
+    Reconnect,
+}
+
+pub(super) type Response = Result<Inbound, ProtocolError>;
+
+/// Unanswered slots are kept this many request timeouts, after which the
+/// response is assumed lost
+const SLOT_LIFETIME: u32 = 4;
+
+struct Pending {
+    id: u64,
+    /// `None` for frames sent without waiting and for requests that gave up,
+    /// whose response is dropped
+    tx: Option<oneshot::Sender<Response>>,
+    sent: Instant,
+}
+
+/// Handle used by commands to talk to Taurus
+///
+/// Every frame Taurus answers registers a pending slot keyed by the command
+/// name as it is written to the socket, and responses are handed to the
+/// oldest slot for that command. Frames sent without waiting get a slot too,
+/// and requests that time out leave theirs behind, so late responses can't
+/// end up with someone else's request.
+#[derive(Clone)]
+pub struct TaurusClient {
+    tx: Sender<TaurusCommand>,
+    pending: Arc<Mutex<HashMap<&'static str, VecDeque<Pending>>>>,
+    next_id: Arc<AtomicU64>,
+    status: Arc<Mutex<TaurusStatus>>,
+    timeout: Duration,
+}
+
+impl TaurusClient {
+    pub fn new(url: String, timeout: Duration) -> (Self, Receiver<TaurusCommand>) {
+        let (tx, rx) = mpsc::channel(100);
+        let client = Self {
+            tx,
+            pending: Arc::new(Mutex::new(HashMap::new())),
+            next_id: Arc::new(AtomicU64::new(0)),
+            status: Arc::new(Mutex::new(TaurusStatus {
+                url,
+                state: ConnectionState::Connecting,
+                auth: AuthState::Pending,
+                last_error: None,
+            })),
+            timeout,
+        };
+        (client, rx)
+    }
+
+    pub async fn state(&self) -> ConnectionState {
+        self.status.lock().await.state.clone()
+    }
+
+    pub async fn status(&self) -> TaurusStatus {
+        self.status.lock().await.clone()
+    }
+
+    pub(super) async fn set_state(&self, state: ConnectionState) {
+        self.status.lock().await.state = state;
+    }
+
+    pub(super) async fn set_auth(&self, auth: AuthState) {
+        self.status.lock().await.auth = auth;
+    }
+
+    pub(super) async fn set_error(&self, error: String) {
+        self.status.lock().await.last_error = Some((error, SystemTime::now()));
+    }
+
+    async fn ensure_connected(&self) -> Result<(), TaurusError> {
+        match self.state().await {
+            ConnectionState::Connected { .. } => Ok(()),
+            state => Err(TaurusError::NotConnected(state)),
+        }
+    }
+
+    /// Sends a frame without waiting for a response
+    pub async fn send(&self, frame: Outbound) -> Result<(), TaurusError> {
+        encode(&frame).map_err(TaurusError::Invalid)?;
+        self.ensure_connected().await?;
+        self.tx
+            .send(TaurusCommand::Send(frame))
+            .await
+            .map_err(|_| TaurusError::Closed)
+    }
+
+    /// Hands a bridged Discord message to the connection task, which queues it
+    /// if Taurus is currently unreachable
+    pub async fn bridge(&self, message: BridgeMessage) -> Result<(), TaurusError> {
+        self.tx
+            .send(TaurusCommand::Bridge(message))
+            .await
+            .map_err(|_| TaurusError::Closed)
+    }
+
+    /// Asks the connection task to drop the current socket and connect again
+    pub async fn reconnect(&self) -> Result<(), TaurusError> {
+        self.tx
+            .send(TaurusCommand::Reconnect)
+            .await
+            .map_err(|_| TaurusError::Closed)
+    }
+
+    /// Sends `frame` and waits for the response frame of the matching type
+    pub async fn request(&self, frame: Outbound) -> Result<Inbound, TaurusError> {
+        let Some(command) = frame.response() else {
+            return Err(TaurusError::NoResponse(frame.command()));
+        };
+        encode(&frame).map_err(TaurusError::Invalid)?;
+        self.ensure_connected().await?;
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = oneshot::channel();
+        self.tx
+            .send(TaurusCommand::Request { frame, id, tx })
+            .await
+            .map_err(|_| TaurusError::Closed)?;
+
+        match tokio::time::timeout(self.timeout, rx).await {
+            Ok(Ok(response)) => response.map_err(TaurusError::Protocol),
+            Ok(Err(_)) => Err(TaurusError::Disconnected),
+            Err(_) => {
+                self.forget(command, id).await;
+                Err(TaurusError::Timeout(command))
+            }
+        }
+    }
+
+    pub async fn list(&self) -> Result<Vec<ServerPlayers>, TaurusError> {
+        match self.request(Outbound::List).await? {
+            Inbound::List(servers) => Ok(servers),
+            other => Err(TaurusError::Unexpected(other.command())),
+        }
+    }
+
+    pub async fn list_sessions(&self) -> Result<Vec<Session>, TaurusError> {
+        match self.request(Outbound::ListSessions).await? {
+            Inbound::ListSessions(sessions) => Ok(sessions),
+            other => Err(TaurusError::Unexpected(other.command())),
+        }
+    }
+
+    pub async fn backup(&self, name: &str) -> Result<String, TaurusError> {
+        match self.request(Outbound::Backup(name.to_string())).await? {
+            Inbound::Backup(response) => Ok(response),
+            other => Err(TaurusError::Unexpected(other.command())),
+        }
+    }
+
+    pub async fn list_backups(&self) -> Result<Vec<BackupEntry>, TaurusError> {
+        match self.request(Outbound::ListBackups).await? {
+            Inbound::ListBackups(response) => Ok(response),
+            other => Err(TaurusError::Unexpected(other.command())),
+        }
+    }
+
+    pub async fn rm_backup(&self, name: &str) -> Result<String, TaurusError> {
+        match self.request(Outbound::RmBackup(name.to_string())).await? {
+            Inbound::RmBackup(response) => Ok(response),
+            other => Err(TaurusError::Unexpected(other.command())),
+        }
+    }
+
+    pub async fn rcon(&self, server: &str, command: &str) -> Result<String, TaurusError> {
+        let frame = Outbound::Rcon {
+            server: server.to_string(),
+            command: command.to_string(),
+        };
+        match self.request(frame).await? {
+            Inbound::Rcon(response) => Ok(response),
+            other => Err(TaurusError::Unexpected(other.command())),
+        }
+    }
+
+    pub async fn start_session(&self, name: &str) -> Result<String, TaurusError> {
+        match self
+            .request(Outbound::StartSession(name.to_string()))
+            .await?
+        {
+            Inbound::StartSession(response) => Ok(response),
+            other => Err(TaurusError::Unexpected(other.command())),
+        }
+    }
+
+    pub async fn stop_session(&self, name: &str) -> Result<String, TaurusError> {
+        match self
+            .request(Outbound::StopSession(name.to_string()))
+            .await?
+        {
+            Inbound::StopSession(response) => Ok(response),
+            other => Err(TaurusError::Unexpected(other.command())),
+        }
+    }
+
+    pub async fn restart_session(&self, name: &str) -> Result<String, TaurusError> {
+        match self
+            .request(Outbound::RestartSession(name.to_string()))
+            .await?
+        {
+            Inbound::RestartSession(response) => Ok(response),
+            other => Err(TaurusError::Unexpected(other.command())),
+        }
+    }
+
+    pub async fn session_status(&self, name: &str) -> Result<SessionStatus, TaurusError> {
+        match self
+            .request(Outbound::SessionStatus(name.to_string()))
+            .await?
+        {
+            Inbound::SessionStatus(status) => Ok(status),
+            other => Err(TaurusError::Unexpected(other.command())),
+        }
+    }
+
+    /// Keeps the slot of a request that gave up, so its response is dropped
+    /// if it still arrives
+    async fn forget(&self, command: &'static str, id: u64) {
+        let mut pending = self.pending.lock().await;
+        if let Some(slot) = pending
+            .get_mut(command)
+            .and_then(|queue| queue.iter_mut().find(|p| p.id == id))
+        {
+            slot.tx = None;
+        }
+    }
+
+    /// Drops the slots Taurus never answered
+    fn expire(&self, queue: &mut VecDeque<Pending>) {
+        let lifetime = self.timeout * SLOT_LIFETIME;
+        while queue.front().is_some_and(|p| p.sent.elapsed() >= lifetime) {
+            queue.pop_front();
+        }
+    }
+
+    /// Registers a slot for the response to a frame about to be written.
+    /// Called by the connection task, so slots are in the order Taurus sees
+    /// the frames. Without `reply`, the response is dropped.
+    pub(super) async fn expect(
+        &self,
+        frame: &Outbound,
+        reply: Option<(u64, oneshot::Sender<Response>)>,
+    ) {
+        let Some(command) = frame.response() else {
+            return;
+        };
+        let (id, tx) = match reply {
+            Some((id, tx)) => (id, Some(tx)),
+            None => (self.next_id.fetch_add(1, Ordering::Relaxed), None),
+        };
+        let mut pending = self.pending.lock().await;
+        let queue = pending.entry(command).or_default();
+        self.expire(queue);
+        queue.push_back(Pending {
+            id,
+            tx,
+            sent: Instant::now(),
+        });
+    }
+
+    /// Hands a response to the oldest slot for that command, dropping it if
+    /// the slot's requester is gone. Returns `false` if there was no slot.
+    pub(super) async fn resolve(&self, command: &'static str, response: Response) -> bool {
+        let mut pending = self.pending.lock().await;
+        let Some(queue) = pending.get_mut(command) else {
+            return false;
+        };
+        self.expire(queue);
+        let Some(waiter) = queue.pop_front() else {
+            return false;
+        };
+        if let Some(tx) = waiter.tx {
+            // A requester that dropped its receiver just loses the response
+            tx.send(response).ok();
+        }
+        true
+    }
+
+    /// Fails every outstanding request, used when the socket goes away
+    pub(super) async fn cancel_pending(&self) {
+        self.pending.lock().await.clear();
+    }
+}
+
+pub struct Taurus;
+
+impl TypeMapKey for Taurus {
+    type Value = TaurusClient;
+}
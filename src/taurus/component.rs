@@ -0,0 +1,109 @@
+use std::fmt;
+
+use serde::Serialize;
+
+/// The named colours of Minecraft chat
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Colour {
+    Black,
+    DarkBlue,
+    DarkGreen,
+    DarkAqua,
+    DarkRed,
+    DarkPurple,
+    Gold,
+    Gray,
+    DarkGray,
+    Blue,
+    Green,
+    Aqua,
+    Red,
+    LightPurple,
+    Yellow,
+    White,
+}
+
+/// What clicking a component does
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "action", content = "value", rename_all = "snake_case")]
+pub enum ClickEvent {
+    OpenUrl(String),
+    RunCommand(String),
+    SuggestCommand(String),
+    CopyToClipboard(String),
+}
+
+/// What hovering over a component shows
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "action", content = "value", rename_all = "snake_case")]
+pub enum HoverEvent {
+    ShowText(Box<TextComponent>),
+}
+
+/// A Minecraft chat component, as taken by `tellraw`. Children inherit the
+/// style of their parent.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextComponent {
+    pub text: String,
+    #[serde(rename = "color", skip_serializing_if = "Option::is_none")]
+    pub colour: Option<Colour>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bold: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub italic: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub click_event: Option<ClickEvent>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hover_event: Option<HoverEvent>,
+    #[serde(rename = "extra", skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<TextComponent>,
+}
+
+impl TextComponent {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn colour(mut self, colour: Colour) -> Self {
+        self.colour = Some(colour);
+        self
+    }
+
+    pub fn bold(mut self) -> Self {
+        self.bold = Some(true);
+        self
+    }
+
+    pub fn italic(mut self) -> Self {
+        self.italic = Some(true);
+        self
+    }
+
+    pub fn click(mut self, event: ClickEvent) -> Self {
+        self.click_event = Some(event);
+        self
+    }
+
+    /// Shows `text` when the component is hovered over
+    pub fn hover(mut self, text: TextComponent) -> Self {
+        self.hover_event = Some(HoverEvent::ShowText(Box::new(text)));
+        self
+    }
+
+    pub fn child(mut self, child: TextComponent) -> Self {
+        self.children.push(child);
+        self
+    }
+}
+
+impl fmt::Display for TextComponent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let json = serde_json::to_string(self).map_err(|_| fmt::Error)?;
+        f.write_str(&json)
+    }
+}
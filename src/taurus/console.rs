@@ -0,0 +1,211 @@
+use std::time::Duration;
+
+use poise::serenity_prelude::{
+    ChannelId, Context, CreateAllowedMentions, CreateEmbed, CreateMessage, Message, ReactionType,
+    Timestamp,
+};
+use regex::Regex;
+use tokio::sync::mpsc::{self, Receiver, Sender, error::TrySendError};
+
+use crate::{
+    commands::staff::{code_block, denied_by, write_audit},
+    config::{Config, ConsoleOpts},
+};
+
+use super::{format::DISCORD_MESSAGE_LIMIT, format::strip_codes, taurus_client};
+
+/// Room left in a message for the code block fences
+const BLOCK_LIMIT: usize = DISCORD_MESSAGE_LIMIT - 8;
+/// Lines held between two posts, later ones are counted as skipped
+const MAX_BUFFERED: usize = 500;
+/// Longest single console line posted
+const LINE_LIMIT: usize = 500;
+
+/// Hands console output to the task posting it in the console channel
+#[derive(Clone)]
+pub struct ConsoleRelay {
+    tx: Option<Sender<String>>,
+    filters: Vec<Regex>,
+}
+
+impl ConsoleRelay {
+    /// Starts the posting task, or a relay that drops everything if the
+    /// console channel is not configured
+    pub fn start(ctx: &Context, opts: Option<ConsoleOpts>) -> Self {
+        let Some(opts) = opts else {
+            return Self {
+                tx: None,
+                filters: Vec::new(),
+            };
+        };
+        let filters = opts
+            .filters
+            .iter()
+            .filter_map(|filter| match Regex::new(filter) {
+                Ok(regex) => Some(regex),
+                Err(e) => {
+                    println!("ERROR: Ignoring console filter {}: {}", filter, e);
+                    None
+                }
+            })
+            .collect();
+        let (tx, rx) = mpsc::channel(MAX_BUFFERED);
+        let ctx = ctx.clone();
+        tokio::spawn(async move {
+            run_console_relay(&ctx, rx, opts).await;
+        });
+        Self {
+            tx: Some(tx),
+            filters,
+        }
+    }
+
+    /// Queues console output, one message per line, unless a filter mutes it
+    pub fn push(&self, output: &str) {
+        let Some(tx) = &self.tx else {
+            return;
+        };
+        for line in output.lines() {
+            let line = strip_codes(line);
+            if line.trim().is_empty() || self.filters.iter().any(|f| f.is_match(&line)) {
+                continue;
+            }
+            match tx.try_send(line) {
+                Ok(()) | Err(TrySendError::Full(_)) => {}
+                Err(TrySendError::Closed(_)) => return,
+            }
+        }
+    }
+}
+
+fn clean_line(line: &str) -> String {
+    let line = line.replace("```", "'''");
+    match line.char_indices().nth(LINE_LIMIT) {
+        Some((end, _)) => format!("{}…", &line[..end]),
+        None => line,
+    }
+}
+
+/// Packs lines into as few code blocks as fit in a Discord message each
+fn blocks(lines: &[String]) -> Vec<String> {
+    let mut blocks = Vec::new();
+    let mut block = String::new();
+    for line in lines {
+        let line = clean_line(line);
+        if !block.is_empty() && block.len() + line.len() + 1 > BLOCK_LIMIT {
+            blocks.push(std::mem::take(&mut block));
+        }
+        if !block.is_empty() {
+            block.push('\n');
+        }
+        block.push_str(&line);
+    }
+    if !block.is_empty() {
+        blocks.push(block);
+    }
+    blocks
+}
+
+async fn post(ctx: &Context, channel: ChannelId, content: String) {
+    let message = CreateMessage::new()
+        .content(content)
+        .allowed_mentions(CreateAllowedMentions::new());
+    if let Err(e) = channel.send_message(&ctx.http, message).await {
+        println!("ERROR: Failed to post to the console channel: {}", e);
+    }
+}
+
+/// Posts the collected console lines every `interval` seconds, at most
+/// `burst` messages at a time
+async fn run_console_relay(ctx: &Context, mut rx: Receiver<String>, opts: ConsoleOpts) {
+    let channel = ChannelId::new(opts.channel);
+    let mut ticker = tokio::time::interval(Duration::from_secs(opts.interval.max(1)));
+    let mut lines = Vec::new();
+    let mut skipped = 0;
+    loop {
+        tokio::select! {
+            line = rx.recv() => match line {
+                Some(line) if lines.len() < MAX_BUFFERED => lines.push(line),
+                Some(_) => skipped += 1,
+                None => return,
+            },
+            _ = ticker.tick() => {
+                if lines.is_empty() {
+                    continue;
+                }
+                let mut blocks = blocks(&lines);
+                lines.clear();
+                if blocks.len() > opts.burst {
+                    for block in blocks.drain(opts.burst..) {
+                        skipped += block.lines().count();
+                    }
+                }
+                for block in blocks {
+                    post(ctx, channel, format!("```{}```", block)).await;
+                }
+                if skipped > 0 {
+                    post(ctx, channel, format!("*{} console lines skipped*", skipped)).await;
+                    skipped = 0;
+                }
+            }
+        }
+    }
+}
+
+/// Runs a message typed in the console channel as a console command. Returns
+/// `false` if the message was not sent in the console channel.
+pub async fn console_input(ctx: &Context, msg: &Message) -> bool {
+    let (server, staff, denied) = {
+        let data = ctx.data.read().await;
+        let config = data.get::<Config>().expect("Config not found");
+        let Some(console) = &config.console else {
+            return false;
+        };
+        if console.channel != msg.channel_id.get() {
+            return false;
+        }
+        (
+            console.server.clone(),
+            config.staff.contains(&msg.author.id.get()),
+            denied_by(&config.rcon_denylist, &msg.content).map(str::to_string),
+        )
+    };
+    // Only staff should be able to post there, but don't trust the permissions
+    if !staff {
+        return true;
+    }
+
+    let taurus = taurus_client(ctx).await;
+    let command = msg.content.trim();
+    let result = match &denied {
+        Some(entry) => Err(format!("Denied by `{}` on the denylist", entry)),
+        None => taurus
+            .rcon(&server, command)
+            .await
+            .map_err(|e| e.to_string()),
+    };
+    let outcome = match &result {
+        Ok(response) => code_block(response),
+        Err(e) => e.clone(),
+    };
+    let emoji = if result.is_ok() { "✅" } else { "❌" };
+    let reaction = ReactionType::Unicode(emoji.to_string());
+    msg.react(&ctx.http, reaction).await.ok();
+    if let Err(e) = msg.reply(&ctx.http, &outcome).await {
+        println!("ERROR: Failed to answer console command: {}", e);
+    }
+
+    let entry = CreateEmbed::new()
+        .title("Console command")
+        .field(
+            "Staff",
+            format!("<@{}> ({})", msg.author.id, msg.author.name),
+            true,
+        )
+        .field("Server", &server, true)
+        .field("Command", code_block(command), false)
+        .field("Result", outcome, false)
+        .timestamp(Timestamp::now());
+    write_audit(ctx, entry).await;
+    true
+}
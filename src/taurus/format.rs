@@ -0,0 +1,472 @@
+use poise::serenity_prelude::{
+    Cache, Message,
+    utils::{ContentSafeOptions, content_safe},
+};
+
+use super::mc_format;
+
+/// Longest line a Minecraft client accepts in chat
+pub const MC_CHAT_LIMIT: usize = 256;
+
+/// Markdown markers and the formatting codes each one maps to. Longer markers
+/// come first so `**` is not read as two `*`.
+const MARKERS: [(&str, &[char]); 6] = [
+    ("***", &['l', 'o']),
+    ("**", &['l']),
+    ("__", &['n']),
+    ("~~", &['m']),
+    ("*", &['o']),
+    ("_", &['o']),
+];
+
+const ESCAPABLE: [char; 6] = ['\\', '*', '_', '~', '`', '|'];
+
+fn is_escaped(text: &str, index: usize) -> bool {
+    text[..index]
+        .chars()
+        .rev()
+        .take_while(|c| *c == '\\')
+        .count()
+        % 2
+        == 1
+}
+
+fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\\'
+            && let Some(next) = chars.peek()
+            && ESCAPABLE.contains(next)
+        {
+            out.push(*next);
+            chars.next();
+            continue;
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// `_` only starts or ends italics at a word boundary, like Discord
+fn at_word_boundary(text: &str, before: usize, after: usize) -> bool {
+    let prev = text[..before].chars().next_back();
+    let next = text[after..].chars().next();
+    !prev.is_some_and(char::is_alphanumeric) || !next.is_some_and(char::is_alphanumeric)
+}
+
+/// An opening marker must be followed by something other than whitespace, so
+/// `2 * 3 * 4` stays as written
+fn opens(text: &str, after: usize) -> bool {
+    text[after..]
+        .chars()
+        .next()
+        .is_some_and(|c| !c.is_whitespace())
+}
+
+/// A closing marker must follow something other than whitespace
+fn closes(text: &str, before: usize) -> bool {
+    text[..before]
+        .chars()
+        .next_back()
+        .is_some_and(|c| !c.is_whitespace())
+}
+
+/// A marker that is part of a longer run of its character, like the `*`s in
+/// `** not bold **`, neither opens nor closes a span
+fn stands_alone(text: &str, start: usize, end: usize, marker: &str) -> bool {
+    let c = marker.chars().next();
+    text[..start].chars().next_back() != c && text[end..].chars().next() != c
+}
+
+/// Finds the first complete markdown span, returning the byte range of the
+/// whole span, the range of its contents and its formatting codes
+fn find_span(text: &str) -> Option<(usize, usize, usize, usize, &'static [char])> {
+    for (start, _) in text.char_indices() {
+        if is_escaped(text, start) {
+            continue;
+        }
+        for (marker, codes) in MARKERS {
+            if !text[start..].starts_with(marker) {
+                continue;
+            }
+            let inner_start = start + marker.len();
+            if !opens(text, inner_start)
+                || !stands_alone(text, start, inner_start, marker)
+                || marker == "_" && !at_word_boundary(text, start, inner_start)
+            {
+                continue;
+            }
+            let closing = text[inner_start..]
+                .match_indices(marker)
+                .map(|(i, _)| inner_start + i)
+                .find(|&i| {
+                    i > inner_start
+                        && !is_escaped(text, i)
+                        && closes(text, i)
+                        && stands_alone(text, i, i + marker.len(), marker)
+                        && (marker != "_" || at_word_boundary(text, i, i + 1))
+                });
+            if let Some(inner_end) = closing {
+                return Some((
+                    start,
+                    inner_start,
+                    inner_end,
+                    inner_end + marker.len(),
+                    codes,
+                ));
+            }
+        }
+    }
+    None
+}
+
+/// Converts markdown spans to formatting codes. `active` holds the codes of
+/// the enclosing spans, which are restored after each `§r`.
+fn translate_spans(text: &str, active: &[char]) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some((start, inner_start, inner_end, end, span_codes)) = find_span(rest) {
+        out.push_str(&unescape(&rest[..start]));
+        let mut codes = active.to_vec();
+        for code in span_codes {
+            if !codes.contains(code) {
+                codes.push(*code);
+            }
+        }
+        let inner = translate_spans(&rest[inner_start..inner_end], &codes);
+        out.push_str(&mc_format(&inner, &codes));
+        for c in active {
+            out.push('§');
+            out.push(*c);
+        }
+        rest = &rest[end..];
+    }
+    out.push_str(&unescape(rest));
+    out
+}
+
+/// Turns a fenced block into one grey line, dropping the language tag
+fn flatten_code(code: &str) -> String {
+    let code = match code.split_once('\n') {
+        Some((lang, body)) if !lang.contains(char::is_whitespace) => body,
+        _ => code,
+    };
+    let flat = code.split_whitespace().collect::<Vec<_>>().join(" ");
+    mc_format(&flat, &['7'])
+}
+
+fn translate_markdown(text: &str) -> String {
+    let mut out = String::new();
+    for (i, block) in text.split("```").enumerate() {
+        if i % 2 == 1 {
+            out.push_str(&flatten_code(block));
+            continue;
+        }
+        for (j, part) in block.split('`').enumerate() {
+            if j % 2 == 1 {
+                out.push_str(&mc_format(part, &['7']));
+            } else {
+                out.push_str(&translate_spans(part, &[]));
+            }
+        }
+    }
+    out
+}
+
+/// Replaces custom emoji (`<:name:id>`, `<a:name:id>`) with `:name:`
+fn translate_emoji(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('<') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let emoji = rest[1..].find('>').and_then(|end| {
+            let inner = &rest[1..end + 1];
+            let inner = inner.strip_prefix('a').unwrap_or(inner);
+            let (name, id) = inner.strip_prefix(':')?.split_once(':')?;
+            let is_id = !id.is_empty() && id.chars().all(|c| c.is_ascii_digit());
+            is_id.then_some((name, end + 2))
+        });
+        match emoji {
+            Some((name, len)) => {
+                out.push(':');
+                out.push_str(name);
+                out.push(':');
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('<');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Translates a Discord message into Minecraft chat text: mentions become
+/// names, custom emoji become `:name:` and markdown becomes formatting codes
+pub fn discord_to_minecraft(cache: &Cache, msg: &Message) -> String {
+    let mut options = ContentSafeOptions::default();
+    if let Some(guild_id) = msg.guild_id {
+        options = options.display_as_member_from(guild_id);
+    }
+    let content = content_safe(cache, &msg.content, &options, &msg.mentions);
+    // Only markdown may format the text, not codes typed into the message
+    let content = content.replace('§', "");
+    translate_markdown(&translate_emoji(&content))
+}
+
+/// Formatting codes in effect at the end of `text`
+fn active_codes(text: &str) -> String {
+    let mut colour = None;
+    let mut formats = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '§' {
+            continue;
+        }
+        match chars.next() {
+            Some('r') => {
+                colour = None;
+                formats.clear();
+            }
+            Some(code @ ('0'..='9' | 'a'..='f')) => {
+                colour = Some(code);
+                formats.clear();
+            }
+            Some(code) if !formats.contains(code) => {
+                formats.push('§');
+                formats.push(code);
+            }
+            _ => {}
+        }
+    }
+    match colour {
+        Some(colour) => format!("§{}{}", colour, formats),
+        None => formats,
+    }
+}
+
+/// Splits text into lines of at most `limit` characters, breaking on spaces
+/// where possible and carrying formatting over to the next line
+pub fn split_chat(text: &str, limit: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    // Characters on the current line, and how many of them are carried codes
+    let mut len = 0;
+    let mut carried = 0;
+    for word in text.split(' ') {
+        let word_len = word.chars().count();
+        let sep = usize::from(len > carried);
+        if len + sep + word_len <= limit {
+            if sep == 1 {
+                line.push(' ');
+            }
+            line.push_str(word);
+            len += sep + word_len;
+            continue;
+        }
+        let mut rest: Vec<char> = word.chars().collect();
+        loop {
+            if len > carried {
+                let carry = active_codes(&line);
+                lines.push(std::mem::replace(&mut line, carry));
+                len = line.chars().count();
+                carried = len;
+            }
+            if len + rest.len() <= limit {
+                break;
+            }
+            let take = limit.saturating_sub(len).max(1).min(rest.len());
+            line.extend(rest.drain(..take));
+            len += take;
+        }
+        line.extend(rest.iter());
+        len += rest.len();
+    }
+    if len > carried {
+        lines.push(line);
+    }
+    lines
+}
+
+/// Longest message Discord accepts
+pub const DISCORD_MESSAGE_LIMIT: usize = 2000;
+
+/// Formatting codes with a markdown equivalent
+const DISCORD_MARKERS: [(char, &str); 4] = [('l', "**"), ('o', "*"), ('m', "~~"), ('n', "__")];
+
+/// Escapes markdown so text such as player names shows up as written
+pub fn escape_markdown(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if ESCAPABLE.contains(&c) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Translates Minecraft chat text for Discord: formatting codes become
+/// markdown and colour codes are dropped
+pub fn minecraft_to_discord(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    // Markers are only opened once there is text for them to wrap
+    let mut pending: Vec<&str> = Vec::new();
+    let mut open: Vec<&str> = Vec::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '§' {
+            for marker in pending.drain(..) {
+                out.push_str(marker);
+                open.push(marker);
+            }
+            out.push(c);
+            continue;
+        }
+        let Some(code) = chars.next().map(|code| code.to_ascii_lowercase()) else {
+            break;
+        };
+        match DISCORD_MARKERS.iter().find(|(c, _)| *c == code) {
+            Some((_, marker)) if !open.contains(marker) && !pending.contains(marker) => {
+                pending.push(marker);
+            }
+            Some(_) => {}
+            // Colour codes reset formatting in Minecraft as well
+            None if code == 'r' || code.is_ascii_hexdigit() => {
+                pending.clear();
+                while let Some(marker) = open.pop() {
+                    out.push_str(marker);
+                }
+            }
+            None => {}
+        }
+    }
+    while let Some(marker) = open.pop() {
+        out.push_str(marker);
+    }
+    out
+}
+
+/// Removes every formatting code from Minecraft text
+pub fn strip_codes(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '§' {
+            chars.next();
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Splits text into messages of at most `limit` characters, breaking on
+/// newlines or spaces where possible
+pub fn split_message(text: &str, limit: usize) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut rest = text;
+    while rest.chars().count() > limit {
+        let end = rest
+            .char_indices()
+            .nth(limit)
+            .map_or(rest.len(), |(i, _)| i);
+        let cut = rest[..end]
+            .rfind('\n')
+            .or_else(|| rest[..end].rfind(' '))
+            .filter(|&i| i > 0)
+            .unwrap_or(end);
+        parts.push(rest[..cut].to_string());
+        rest = rest[cut..].trim_start_matches(['\n', ' ']);
+    }
+    if !rest.is_empty() {
+        parts.push(rest.to_string());
+    }
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn translates_markdown_spans() {
+        assert_eq!(translate_markdown("**bold**"), "§lbold§r");
+        assert_eq!(
+            translate_markdown("*italic* and _too_"),
+            "§oitalic§r and §otoo§r"
+        );
+        assert_eq!(
+            translate_markdown("__under__ ~~gone~~"),
+            "§nunder§r §mgone§r"
+        );
+        assert_eq!(translate_markdown("***both***"), "§l§oboth§r");
+        assert_eq!(translate_markdown("**a *b* c**"), "§la §l§ob§r§l c§r");
+        assert_eq!(translate_markdown("*a **b** c*"), "§oa §o§lb§r§o c§r");
+    }
+
+    #[test]
+    fn leaves_stray_markers_alone() {
+        assert_eq!(translate_markdown("2 * 3 * 4"), "2 * 3 * 4");
+        assert_eq!(translate_markdown("** not bold **"), "** not bold **");
+        assert_eq!(translate_markdown("snake_case_name"), "snake_case_name");
+        assert_eq!(translate_markdown("*unclosed"), "*unclosed");
+        assert_eq!(translate_markdown(r"\*escaped\*"), "*escaped*");
+    }
+
+    #[test]
+    fn greys_out_code() {
+        assert_eq!(translate_markdown("run `**x**`"), "run §7**x**§r");
+        assert_eq!(
+            translate_markdown("```rs\nfn  main()\n```"),
+            "§7fn main()§r"
+        );
+    }
+
+    #[test]
+    fn splits_chat_on_spaces() {
+        assert_eq!(split_chat("hello world", 20), ["hello world"]);
+        assert_eq!(
+            split_chat("hello world again", 11),
+            ["hello world", "again"]
+        );
+        assert_eq!(split_chat("abcdefghij", 4), ["abcd", "efgh", "ij"]);
+        assert!(split_chat("", 10).is_empty());
+    }
+
+    #[test]
+    fn carries_formatting_to_the_next_line() {
+        assert_eq!(split_chat("§lhello world", 8), ["§lhello", "§lworld"]);
+        assert_eq!(active_codes("§ared §lbold"), "§a§l");
+        assert_eq!(active_codes("§lbold§r plain"), "");
+    }
+
+    #[test]
+    fn translates_minecraft_codes() {
+        assert_eq!(minecraft_to_discord("§lbold§r plain"), "**bold** plain");
+        assert_eq!(minecraft_to_discord("§aGreen"), "Green");
+        assert_eq!(minecraft_to_discord("§oitalic"), "*italic*");
+        assert_eq!(minecraft_to_discord("§l§r"), "");
+        assert_eq!(minecraft_to_discord("§L§ObOTH"), "***bOTH***");
+    }
+
+    #[test]
+    fn escapes_and_strips() {
+        assert_eq!(escape_markdown("a_b*c"), r"a\_b\*c");
+        assert_eq!(strip_codes("§aHi §lthere"), "Hi there");
+        assert_eq!(strip_codes("trailing §"), "trailing ");
+    }
+
+    #[test]
+    fn splits_messages_on_newlines_then_spaces() {
+        assert_eq!(split_message("short", 10), ["short"]);
+        assert_eq!(split_message("aaa bb\nccc", 8), ["aaa bb", "ccc"]);
+        assert_eq!(split_message("aaa bbb", 5), ["aaa", "bbb"]);
+        assert_eq!(split_message("abcdefgh", 3), ["abc", "def", "gh"]);
+        assert!(split_message("", 10).is_empty());
+    }
+}
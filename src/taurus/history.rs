@@ -0,0 +1,56 @@
+use std::collections::VecDeque;
+
+use poise::serenity_prelude::{MessageId, prelude::TypeMapKey};
+
+use super::BridgeTarget;
+
+/// A Discord message that was bridged to Minecraft
+#[derive(Debug, Clone)]
+pub struct BridgedMessage {
+    pub target: BridgeTarget,
+    pub author: String,
+}
+
+/// Recently bridged Discord messages, so edits and deletions can follow them
+pub struct BridgeHistory {
+    entries: VecDeque<(MessageId, BridgedMessage)>,
+    capacity: usize,
+}
+
+impl BridgeHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    /// Remembers a message, forgetting the oldest one if the history is full
+    pub fn record(&mut self, id: MessageId, message: BridgedMessage) {
+        if self.capacity == 0 {
+            return;
+        }
+        while self.entries.len() >= self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back((id, message));
+    }
+
+    pub fn get(&self, id: MessageId) -> Option<&BridgedMessage> {
+        self.entries
+            .iter()
+            .find(|(entry, _)| *entry == id)
+            .map(|(_, message)| message)
+    }
+
+    pub fn remove(&mut self, id: MessageId) -> Option<BridgedMessage> {
+        let index = self.entries.iter().position(|(entry, _)| *entry == id)?;
+        self.entries.remove(index).map(|(_, message)| message)
+    }
+}
+
+pub struct History;
+
+impl TypeMapKey for History {
+    type Value = BridgeHistory;
+}
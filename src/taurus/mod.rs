@@ -0,0 +1,577 @@
+mod backoff;
+mod bridge;
+mod client;
+mod component;
+mod console;
+mod format;
+mod history;
+pub mod protocol;
+mod queue;
+
+use std::{
+    env,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use futures::{FutureExt, SinkExt, StreamExt};
+use http::Uri;
+use poise::serenity_prelude::{ChannelId, Context, CreateMessage, ReactionType};
+use tokio::{
+    net::TcpStream,
+    sync::{mpsc::Receiver, oneshot},
+};
+use tokio_websockets::{ClientBuilder, MaybeTlsStream, Message as WSMessage, WebSocketStream};
+
+use crate::{
+    commands::ingame::execute_ingame_command,
+    config::{Config, TaurusOpts},
+    links::Links,
+};
+
+use protocol::{ChatLine, Inbound, Outbound, ProtocolError, encode, is_player_name, parse};
+
+use backoff::Backoff;
+use bridge::Bridge;
+use client::Response;
+use console::ConsoleRelay;
+use queue::{BridgeMessage, BridgeQueue};
+
+pub use bridge::{BridgeTarget, send_edit, send_message, send_removal};
+pub use component::{ClickEvent, Colour, TextComponent};
+pub use console::console_input;
+pub use history::{BridgeHistory, BridgedMessage, History};
+pub use client::{
+    AuthState, ConnectionState, Taurus, TaurusClient, TaurusCommand, TaurusError, TaurusStatus,
+};
+
+/// The Taurus client shared through the context data
+pub async fn taurus_client(ctx: &Context) -> TaurusClient {
+    let data = ctx.data.read().await;
+    data.get::<Taurus>().expect("Taurus not found").clone()
+}
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+async fn send_frame(ws: &mut Socket, frame: &Outbound) -> Result<(), tokio_websockets::Error> {
+    // The client checks frames before handing them over, so this is a bug
+    match encode(frame) {
+        Ok(text) => ws.send(WSMessage::text(text)).await,
+        Err(e) => {
+            println!("ERROR: Not sending {} frame: {}", frame.command(), e);
+            Ok(())
+        }
+    }
+}
+
+/// How long Taurus gets to answer the authentication ping
+const AUTH_TIMEOUT: Duration = Duration::from_secs(10);
+/// A connection that stayed up this long resets the reconnection backoff
+const STABLE_AFTER: Duration = Duration::from_secs(60);
+
+enum ConnectError {
+    Unreachable(String),
+    AuthFailed(String),
+}
+
+/// Why a live connection was dropped
+enum Dropped {
+    /// Someone ran `/reconnect`
+    Requested,
+    Lost(String),
+}
+
+/// Authenticates with Taurus, returning the frames it sent before the answer
+async fn auth_taurus(ws: &mut Socket) -> Result<(AuthState, Vec<Inbound>), ConnectError> {
+    let Ok(password) = env::var("TAURUS_PASS") else {
+        println!("WARN: TAURUS_PASS environment variable not set, connecting unauthenticated");
+        return Ok((AuthState::Unconfigured, Vec::new()));
+    };
+
+    let unreachable = |e: tokio_websockets::Error| ConnectError::Unreachable(e.to_string());
+    send_frame(ws, &Outbound::Auth(password)).await.map_err(unreachable)?;
+    send_frame(ws, &Outbound::Ping).await.map_err(unreachable)?;
+    tokio::time::timeout(AUTH_TIMEOUT, await_pong(ws))
+        .await
+        .unwrap_or_else(|_| {
+            Err(ConnectError::Unreachable(
+                "timed out waiting for PONG".to_string(),
+            ))
+        })
+}
+
+/// Reads frames until the PONG answering the authentication ping, keeping the
+/// traffic sent before it. Taurus closing the socket or sending a frame
+/// outside the protocol is taken as a rejection of the password.
+async fn await_pong(ws: &mut Socket) -> Result<(AuthState, Vec<Inbound>), ConnectError> {
+    let mut early = Vec::new();
+    while let Some(msg) = ws.next().await {
+        let msg = msg.map_err(|e| ConnectError::Unreachable(e.to_string()))?;
+        if msg.is_close() {
+            break;
+        }
+        let Some(text) = msg.as_text() else {
+            continue;
+        };
+        match parse(text) {
+            Ok(Inbound::Pong) => return Ok((AuthState::Authenticated, early)),
+            Ok(Inbound::Ping) => send_frame(ws, &Outbound::Pong)
+                .await
+                .map_err(|e| ConnectError::Unreachable(e.to_string()))?,
+            Ok(frame) => early.push(frame),
+            Err(ProtocolError::UnknownCommand(_)) => {
+                return Err(ConnectError::AuthFailed(format!(
+                    "Taurus answered {}",
+                    text.trim()
+                )));
+            }
+            Err(e) => println!("WARN: Ignoring frame during authentication: {}", e),
+        }
+    }
+    Err(ConnectError::AuthFailed(
+        "Taurus closed the connection during authentication".to_string(),
+    ))
+}
+
+async fn connect(uri_str: &str) -> Result<(Socket, AuthState, Vec<Inbound>), ConnectError> {
+    let uri = Uri::from_str(uri_str)
+        .map_err(|e| ConnectError::Unreachable(format!("invalid TAURUS_URL: {}", e)))?;
+    let (mut ws, _res) = ClientBuilder::from_uri(uri)
+        .connect()
+        .await
+        .map_err(|e| ConnectError::Unreachable(format!("failed to connect: {}", e)))?;
+    println!("INFO: Connected to Taurus at {}", uri_str);
+    println!("INFO: Authenticating with Taurus...");
+    let (auth, early) = auth_taurus(&mut ws).await?;
+    Ok((ws, auth, early))
+}
+
+/// Posts a notice to the configured staff channel, if there is one
+pub(crate) async fn report_staff(ctx: &Context, message: CreateMessage) {
+    let channel = {
+        let data = ctx.data.read().await;
+        let config = data.get::<Config>().expect("Config not found");
+        config.staff_channel.map(ChannelId::new)
+    };
+    let Some(channel) = channel else {
+        return;
+    };
+    if let Err(e) = channel.send_message(&ctx.http, message).await {
+        println!("ERROR: Failed to report to staff channel: {}", e);
+    }
+}
+
+fn ingame_command<'a>(prefixes: &[String], chat: &'a ChatLine) -> Option<(&'a str, Vec<&'a str>)> {
+    if let Some(rest) = chat.text.strip_prefix('=') {
+        return Some(("eval", rest.split(' ').collect()));
+    }
+    for prefix in prefixes {
+        let Some(rest) = chat.text.strip_prefix(prefix.as_str()) else {
+            continue;
+        };
+        let mut split = rest.split(' ');
+        let cmd = split.next()?;
+        return Some((cmd, split.collect()));
+    }
+    None
+}
+
+/// Whether the chat line is a pending link code
+async fn is_link_code(ctx: &Context, chat: &ChatLine) -> bool {
+    // The name ends up in a tellraw command and in the stored links
+    if !is_player_name(&chat.username) {
+        return false;
+    }
+    let data = ctx.data.read().await;
+    let links = data.get::<Links>().expect("Links not found");
+    links.is_pending(&chat.text)
+}
+
+/// Links the player's account to whoever was issued the code they typed
+async fn confirm_link(ctx: &Context, client: &TaurusClient, chat: &ChatLine) {
+    let confirmed = {
+        let mut data = ctx.data.write().await;
+        let links = data.get_mut::<Links>().expect("Links not found");
+        links.confirm(&chat.text, &chat.username)
+    };
+    let user = match confirmed {
+        Ok(Some(user)) => user,
+        Ok(None) => return,
+        Err(e) => {
+            println!("ERROR: Failed to save account link: {}", e);
+            return;
+        }
+    };
+    let name = match user.to_user(ctx).await {
+        Ok(user) => user.name,
+        Err(_) => user.to_string(),
+    };
+    println!("INFO: Linked {} to Discord user {}", chat.username, name);
+    let text = TextComponent::new(format!("Linked to Discord user {}", name)).colour(Colour::Green);
+    let feedback = Outbound::Rcon {
+        server: chat.server.clone(),
+        command: format!("tellraw {} {}", chat.username, text),
+    };
+    if let Err(e) = client.send(feedback).await {
+        println!("ERROR: Failed to confirm account link in-game: {}", e);
+    }
+}
+
+/// Where frames that don't answer a request end up
+struct Relays {
+    bridge: Bridge,
+    console: ConsoleRelay,
+}
+
+/// Handles one frame received from Taurus
+async fn dispatch(
+    ctx: &Context,
+    client: &TaurusClient,
+    relays: &Relays,
+    frame: Result<Inbound, ProtocolError>,
+) {
+    let Relays { bridge, console } = relays;
+    match frame {
+        Ok(Inbound::Chat(chat)) => {
+            // Link codes are not relayed, so nobody else can claim them.
+            // Linking waits on Discord and Taurus, so not in this loop.
+            if is_link_code(ctx, &chat).await {
+                let (ctx, client) = (ctx.clone(), client.clone());
+                tokio::spawn(async move {
+                    confirm_link(&ctx, &client, &chat).await;
+                });
+                return;
+            }
+            if let Some((cmd, args)) = ingame_command(&bridge.cmd_prefix, &chat) {
+                // Commands wait on replies from Taurus, which this loop reads
+                let ctx = ctx.clone();
+                let (server, username) = (chat.server.clone(), chat.username.clone());
+                let cmd = cmd.to_string();
+                let args = args.into_iter().map(str::to_string).collect::<Vec<_>>();
+                tokio::spawn(async move {
+                    let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+                    if let Err(e) =
+                        execute_ingame_command(&ctx, &server, &username, &cmd, &args).await
+                    {
+                        println!("ERROR: In-game command {} failed: {}", cmd, e);
+                    }
+                });
+            }
+            bridge.relay_chat(ctx, &chat).await;
+        }
+        Ok(Inbound::Msg(body)) => {
+            console.push(&body);
+            bridge.relay_msg(ctx, &body).await;
+        }
+        Ok(Inbound::Event(event)) => bridge.relay_event(ctx, &event).await,
+        Ok(Inbound::Console(output)) => console.push(&output),
+        Ok(response) => {
+            let command = response.command();
+            // Nobody waits for RCON output of commands run on the server itself
+            let output = match &response {
+                Inbound::Rcon(output) => Some(output.clone()),
+                _ => None,
+            };
+            if client.resolve(command, Ok(response)).await {
+                return;
+            }
+            match output {
+                Some(output) => console.push(&output),
+                None => println!("INFO: Ignoring unsolicited Taurus {} frame", command),
+            }
+        }
+        Err(error @ ProtocolError::Malformed { command, .. }) => {
+            println!("ERROR: {}", error);
+            client.resolve(command, Err(error)).await;
+        }
+        Err(e) => println!("ERROR: {}", e),
+    }
+}
+
+/// Writes a frame, first registering where its response goes
+async fn write_frame(
+    ws: &mut Socket,
+    client: &TaurusClient,
+    frame: &Outbound,
+    reply: Option<(u64, oneshot::Sender<Response>)>,
+) -> Result<(), tokio_websockets::Error> {
+    client.expect(frame, reply).await;
+    send_frame(ws, frame).await
+}
+
+async fn send_bridge(
+    ws: &mut Socket,
+    client: &TaurusClient,
+    message: &BridgeMessage,
+) -> Result<(), tokio_websockets::Error> {
+    for frame in &message.frames {
+        write_frame(ws, client, frame, None).await?;
+    }
+    Ok(())
+}
+
+/// Marks bridged messages that never made it to Minecraft
+async fn report_dropped(ctx: &Context, dropped: Vec<BridgeMessage>) {
+    if dropped.is_empty() {
+        return;
+    }
+    println!("INFO: Dropped {} queued bridge messages", dropped.len());
+    for message in dropped {
+        let Some((channel, id)) = message.origin else {
+            continue;
+        };
+        let reaction = ReactionType::Unicode("❌".to_string());
+        if let Err(e) = channel.create_reaction(&ctx.http, id, reaction).await {
+            println!("ERROR: Failed to mark dropped message: {}", e);
+        }
+    }
+}
+
+async fn flush_queue(
+    ctx: &Context,
+    client: &TaurusClient,
+    ws: &mut Socket,
+    queue: &mut BridgeQueue,
+) -> Result<(), String> {
+    report_dropped(ctx, queue.expire()).await;
+    if !queue.is_empty() {
+        println!("INFO: Flushing {} queued bridge messages", queue.len());
+    }
+    while let Some(message) = queue.pop() {
+        if let Err(e) = send_bridge(ws, client, &message).await {
+            queue.requeue(message);
+            return Err(format!("failed to flush bridge queue: {}", e));
+        }
+    }
+    Ok(())
+}
+
+/// Waits out a reconnection delay, queueing bridge traffic meanwhile. Without
+/// a delay it waits for a manual reconnect only. Returns `true` if a reconnect
+/// was requested in the meantime.
+async fn wait_for_retry(
+    ctx: &Context,
+    rx: &mut Receiver<TaurusCommand>,
+    queue: &mut BridgeQueue,
+    delay: Option<Duration>,
+) -> bool {
+    let sleep = match delay {
+        Some(delay) => tokio::time::sleep(delay).boxed(),
+        None => futures::future::pending().boxed(),
+    };
+    tokio::pin!(sleep);
+    loop {
+        tokio::select! {
+            _ = &mut sleep => return false,
+            cmd = rx.recv() => match cmd {
+                Some(TaurusCommand::Reconnect) => return true,
+                Some(TaurusCommand::Bridge(message)) => {
+                    report_dropped(ctx, queue.push(message)).await;
+                }
+                Some(TaurusCommand::Send(frame) | TaurusCommand::Request { frame, .. }) => {
+                    println!("ERROR: No active connection to Taurus, dropping {} frame", frame.command());
+                }
+                None => {
+                    (&mut sleep).await;
+                    return false;
+                }
+            },
+        }
+    }
+}
+
+/// Drives one live connection until it has to be dropped, returning why
+async fn run_connection(
+    ctx: &Context,
+    client: &TaurusClient,
+    rx: &mut Receiver<TaurusCommand>,
+    ws: &mut Socket,
+    queue: &mut BridgeQueue,
+    relays: &Relays,
+    heartbeat: Duration,
+) -> Dropped {
+    let mut heartbeat = tokio::time::interval(heartbeat);
+    heartbeat.tick().await;
+    let mut awaiting_pong = false;
+    if let Err(reason) = flush_queue(ctx, client, ws, queue).await {
+        return Dropped::Lost(reason);
+    }
+    loop {
+        tokio::select! {
+            Some(cmd) = rx.recv() => match cmd {
+                TaurusCommand::Reconnect => return Dropped::Requested,
+                TaurusCommand::Send(frame) => {
+                    if let Err(e) = write_frame(ws, client, &frame, None).await {
+                        return Dropped::Lost(format!("failed to send message: {}", e));
+                    }
+                }
+                TaurusCommand::Request { frame, id, tx } => {
+                    if let Err(e) = write_frame(ws, client, &frame, Some((id, tx))).await {
+                        return Dropped::Lost(format!("failed to send message: {}", e));
+                    }
+                }
+                TaurusCommand::Bridge(message) => {
+                    if let Err(e) = send_bridge(ws, client, &message).await {
+                        report_dropped(ctx, queue.push(message)).await;
+                        return Dropped::Lost(format!("failed to send message: {}", e));
+                    }
+                }
+            },
+            msg = ws.next() => match msg {
+                Some(Ok(msg)) => {
+                    // Any traffic proves the socket is alive
+                    awaiting_pong = false;
+                    let Some(text) = msg.as_text() else {
+                        continue;
+                    };
+                    match parse(text) {
+                        Ok(Inbound::Ping) => {
+                            if let Err(e) = send_frame(ws, &Outbound::Pong).await {
+                                return Dropped::Lost(format!("failed to answer ping: {}", e));
+                            }
+                        }
+                        Ok(Inbound::Pong) => {
+                            client.resolve("PONG", Ok(Inbound::Pong)).await;
+                        }
+                        frame => dispatch(ctx, client, relays, frame).await,
+                    }
+                }
+                Some(Err(e)) => return Dropped::Lost(format!("failed to receive message: {}", e)),
+                None => return Dropped::Lost("connection closed".to_string()),
+            },
+            _ = heartbeat.tick() => {
+                if awaiting_pong {
+                    return Dropped::Lost("heartbeat timed out".to_string());
+                }
+                if let Err(e) = send_frame(ws, &Outbound::Ping).await {
+                    return Dropped::Lost(format!("failed to send heartbeat: {}", e));
+                }
+                awaiting_pong = true;
+            }
+        }
+    }
+}
+
+pub async fn taurus_connection(
+    ctx: &Context,
+    mut rx: Receiver<TaurusCommand>,
+    client: TaurusClient,
+) {
+    let taurus_url = client.status().await.url;
+    let (relays, opts) = {
+        let data = ctx.data.read().await;
+        let config = data.get::<Config>().expect("Could not find Config");
+        let relays = Relays {
+            bridge: Bridge::from_config(config),
+            console: ConsoleRelay::start(ctx, config.console.clone()),
+        };
+        (relays, config.taurus.clone())
+    };
+    let TaurusOpts {
+        reconnect_min,
+        reconnect_max,
+        heartbeat,
+        queue_size,
+        queue_max_age,
+        ..
+    } = opts;
+    let mut queue = BridgeQueue::new(queue_size, Duration::from_secs(queue_max_age));
+    let mut backoff = Backoff::new(
+        Duration::from_secs(reconnect_min),
+        Duration::from_secs(reconnect_max),
+    );
+    loop {
+        let (mut ws, early) = match connect(&taurus_url).await {
+            Ok((ws, auth, early)) => {
+                client.set_auth(auth).await;
+                (ws, early)
+            }
+            Err(ConnectError::AuthFailed(reason)) => {
+                println!("ERROR: Failed to authenticate with Taurus: {}", reason);
+                client.set_auth(AuthState::Failed(reason.clone())).await;
+                client.set_error(reason.clone()).await;
+                client.set_state(ConnectionState::AuthFailed).await;
+                let notice = format!(
+                    "Taurus authentication failed: {}\nNot retrying until someone runs `/reconnect`.",
+                    reason
+                );
+                report_staff(ctx, CreateMessage::new().content(notice)).await;
+                wait_for_retry(ctx, &mut rx, &mut queue, None).await;
+                backoff.reset();
+                continue;
+            }
+            Err(ConnectError::Unreachable(reason)) => {
+                println!("ERROR: Failed to reach Taurus at {}: {}", taurus_url, reason);
+                client.set_error(reason).await;
+                let delay = backoff.next_delay();
+                client
+                    .set_state(ConnectionState::Reconnecting {
+                        attempt: backoff.attempt(),
+                        retry_at: Instant::now() + delay,
+                    })
+                    .await;
+                println!("INFO: Retrying Taurus connection in {}s", delay.as_secs());
+                if wait_for_retry(ctx, &mut rx, &mut queue, Some(delay)).await {
+                    backoff.reset();
+                }
+                continue;
+            }
+        };
+        let connected_at = Instant::now();
+        client
+            .set_state(ConnectionState::Connected {
+                since: connected_at,
+            })
+            .await;
+        for frame in early {
+            dispatch(ctx, &client, &relays, Ok(frame)).await;
+        }
+        let dropped = run_connection(
+            ctx,
+            &client,
+            &mut rx,
+            &mut ws,
+            &mut queue,
+            &relays,
+            Duration::from_secs(heartbeat.max(1)),
+        )
+        .await;
+        client.cancel_pending().await;
+        if connected_at.elapsed() >= STABLE_AFTER {
+            backoff.reset();
+        }
+        let reason = match dropped {
+            Dropped::Requested => {
+                println!("INFO: Dropping Taurus connection: reconnect requested");
+                backoff.reset();
+                client.set_state(ConnectionState::Connecting).await;
+                continue;
+            }
+            Dropped::Lost(reason) => reason,
+        };
+        println!("INFO: Dropping Taurus connection: {}", reason);
+        client.set_error(reason).await;
+        let delay = backoff.next_delay();
+        client
+            .set_state(ConnectionState::Reconnecting {
+                attempt: backoff.attempt(),
+                retry_at: Instant::now() + delay,
+            })
+            .await;
+        println!("INFO: Reconnecting to Taurus in {}s", delay.as_secs());
+        if wait_for_retry(ctx, &mut rx, &mut queue, Some(delay)).await {
+            backoff.reset();
+        }
+    }
+}
+
+pub fn mc_format(msg: &str, color: &[char]) -> String {
+    let mut formatted = String::new();
+    for c in color {
+        formatted.push('§');
+        formatted.push(*c);
+    }
+    formatted.push_str(msg);
+    formatted.push_str("§r");
+    formatted
+}
@@ -0,0 +1,659 @@
+use std::fmt;
+
+use serde::Deserialize;
+
+use super::format::strip_codes;
+
+#[derive(Debug)]
+pub enum ProtocolError {
+    /// The frame had no command word at all
+    Empty,
+    /// The command word is not one we know about
+    UnknownCommand(String),
+    /// The command is known but its body could not be parsed, or a frame to
+    /// send could not be built
+    Malformed {
+        command: &'static str,
+        reason: String,
+    },
+}
+
+impl fmt::Display for ProtocolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProtocolError::Empty => write!(f, "empty Taurus frame"),
+            ProtocolError::UnknownCommand(command) => {
+                write!(f, "unknown Taurus command {}", command)
+            }
+            ProtocolError::Malformed { command, reason } => {
+                write!(f, "malformed {} frame: {}", command, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ProtocolError {}
+
+/// A chat line relayed by the bridge, `[server] <username> text`
+#[derive(Debug, Clone)]
+pub struct ChatLine {
+    pub server: String,
+    pub username: String,
+    pub text: String,
+}
+
+/// The players online on one server, as reported by `LIST`
+#[derive(Debug, Clone)]
+pub struct ServerPlayers {
+    pub server: String,
+    pub players: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EventKind {
+    Join,
+    Leave,
+    Death,
+    Advancement,
+}
+
+/// Something that happened to a player on a server, from an `EVENT` frame
+/// or a recognised server log line
+#[derive(Debug, Clone, Deserialize)]
+pub struct ServerEvent {
+    pub server: Option<String>,
+    pub kind: EventKind,
+    pub player: String,
+    /// The message as the server printed it, e.g. `Steve fell from a high place`
+    pub message: String,
+}
+
+/// One backup reported by `LIST_BACKUPS`
+#[derive(Debug, Clone, Deserialize)]
+pub struct BackupEntry {
+    pub name: String,
+    #[serde(default)]
+    pub date: Option<String>,
+    #[serde(default)]
+    pub size: Option<String>,
+}
+
+/// RCON settings of a session. Only whether they are present matters to the
+/// bot, so the fields themselves are ignored.
+#[derive(Debug, Deserialize, Clone)]
+pub struct Rcon {}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct Game {
+    pub chat_bridge: Option<bool>,
+    pub backup_interval: Option<u64>,
+    pub backup_keep: Option<u64>,
+}
+
+/// The live state of a session, as reported by `SESSION_STATUS`
+#[derive(Debug, Clone, Deserialize)]
+pub struct SessionStatus {
+    pub name: String,
+    pub running: bool,
+    #[serde(default)]
+    pub players: Option<Vec<String>>,
+    /// Seconds since the session was started
+    #[serde(default)]
+    pub uptime: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct Session {
+    pub name: String,
+    pub description: Option<String>,
+    pub host: String,
+    pub game: Option<Game>,
+    pub rcon: Option<Rcon>,
+}
+
+/// Frames received from Taurus
+#[derive(Debug, Clone)]
+pub enum Inbound {
+    /// A `MSG` frame that looks like a player chat line
+    Chat(ChatLine),
+    /// An `EVENT` frame, or a `MSG` frame that looks like a server event
+    Event(ServerEvent),
+    /// Any other `MSG` frame
+    Msg(String),
+    /// Server console output, which may span several lines
+    Console(String),
+    Rcon(String),
+    List(Vec<ServerPlayers>),
+    ListSessions(Vec<Session>),
+    Backup(String),
+    ListBackups(Vec<BackupEntry>),
+    RmBackup(String),
+    StartSession(String),
+    StopSession(String),
+    RestartSession(String),
+    SessionStatus(SessionStatus),
+    Ping,
+    Pong,
+}
+
+/// Frames sent to Taurus
+#[derive(Debug, Clone)]
+pub enum Outbound {
+    /// The password, sent bare as the first frame of a connection
+    Auth(String),
+    Msg(String),
+    Rcon {
+        server: String,
+        command: String,
+    },
+    Url {
+        url: String,
+        label: String,
+    },
+    List,
+    ListSessions,
+    Backup(String),
+    ListBackups,
+    RmBackup(String),
+    StartSession(String),
+    StopSession(String),
+    RestartSession(String),
+    SessionStatus(String),
+    Ping,
+    Pong,
+}
+
+impl Inbound {
+    pub fn command(&self) -> &'static str {
+        match self {
+            Inbound::Chat(_) | Inbound::Msg(_) => "MSG",
+            Inbound::Event(_) => "EVENT",
+            Inbound::Console(_) => "CONSOLE",
+            Inbound::Rcon(_) => "RCON",
+            Inbound::List(_) => "LIST",
+            Inbound::ListSessions(_) => "LIST_SESSIONS",
+            Inbound::Backup(_) => "BACKUP",
+            Inbound::ListBackups(_) => "LIST_BACKUPS",
+            Inbound::RmBackup(_) => "RM_BACKUP",
+            Inbound::StartSession(_) => "START_SESSION",
+            Inbound::StopSession(_) => "STOP_SESSION",
+            Inbound::RestartSession(_) => "RESTART_SESSION",
+            Inbound::SessionStatus(_) => "SESSION_STATUS",
+            Inbound::Ping => "PING",
+            Inbound::Pong => "PONG",
+        }
+    }
+}
+
+impl Outbound {
+    pub fn command(&self) -> &'static str {
+        match self {
+            Outbound::Auth(_) => "AUTH",
+            Outbound::Msg(_) => "MSG",
+            Outbound::Rcon { .. } => "RCON",
+            Outbound::Url { .. } => "URL",
+            Outbound::List => "LIST",
+            Outbound::ListSessions => "LIST_SESSIONS",
+            Outbound::Backup(_) => "BACKUP",
+            Outbound::ListBackups => "LIST_BACKUPS",
+            Outbound::RmBackup(_) => "RM_BACKUP",
+            Outbound::StartSession(_) => "START_SESSION",
+            Outbound::StopSession(_) => "STOP_SESSION",
+            Outbound::RestartSession(_) => "RESTART_SESSION",
+            Outbound::SessionStatus(_) => "SESSION_STATUS",
+            Outbound::Ping => "PING",
+            Outbound::Pong => "PONG",
+        }
+    }
+
+    /// The command of the frame Taurus answers this one with, if any
+    pub fn response(&self) -> Option<&'static str> {
+        match self {
+            Outbound::Rcon { .. } => Some("RCON"),
+            Outbound::List => Some("LIST"),
+            Outbound::ListSessions => Some("LIST_SESSIONS"),
+            Outbound::Backup(_) => Some("BACKUP"),
+            Outbound::ListBackups => Some("LIST_BACKUPS"),
+            Outbound::RmBackup(_) => Some("RM_BACKUP"),
+            Outbound::StartSession(_) => Some("START_SESSION"),
+            Outbound::StopSession(_) => Some("STOP_SESSION"),
+            Outbound::RestartSession(_) => Some("RESTART_SESSION"),
+            Outbound::SessionStatus(_) => Some("SESSION_STATUS"),
+            Outbound::Ping => Some("PONG"),
+            Outbound::Auth(_) | Outbound::Msg(_) | Outbound::Url { .. } | Outbound::Pong => None,
+        }
+    }
+}
+
+fn parse_chat(body: &str) -> Option<ChatLine> {
+    let input = body.strip_prefix('[')?;
+    let (server, rest) = input.split_once(']')?;
+    let rest = rest.trim_start();
+    let rest = rest.strip_prefix('<')?;
+    let (username, rest) = rest.split_once('>')?;
+
+    Some(ChatLine {
+        server: server.to_string(),
+        username: username.to_string(),
+        text: rest.trim_start().to_string(),
+    })
+}
+
+/// Death messages all start with one of these after the player's name
+const DEATH_PHRASES: &[&str] = &[
+    "was slain by",
+    "was shot by",
+    "was killed",
+    "was blown up",
+    "was pummeled",
+    "was fireballed",
+    "was pricked to death",
+    "was squashed",
+    "was squished",
+    "was impaled",
+    "was skewered",
+    "was stung to death",
+    "was poked to death",
+    "was struck by lightning",
+    "was frozen to death",
+    "was burnt to a crisp",
+    "was roasted",
+    "was obliterated",
+    "was doomed to fall",
+    "was knocked into the void",
+    "died",
+    "drowned",
+    "blew up",
+    "burned to death",
+    "hit the ground too hard",
+    "fell from",
+    "fell off",
+    "fell out of",
+    "fell too far",
+    "fell while",
+    "went up in flames",
+    "walked into",
+    "tried to swim in lava",
+    "suffocated",
+    "starved",
+    "froze to death",
+    "experienced kinetic energy",
+    "withered away",
+    "discovered the floor was lava",
+    "didn't want to live",
+    "went off with a bang",
+    "left the confines of this world",
+    "walked on danger zone",
+];
+
+/// Whether `name` is a valid Minecraft username
+pub fn is_player_name(name: &str) -> bool {
+    (3..=16).contains(&name.len()) && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Recognises join, leave, death and advancement lines from the server log.
+/// Only lines Taurus prefixed with `[server]` count, so other messages that
+/// happen to read like one stay messages.
+fn parse_event(body: &str) -> Option<ServerEvent> {
+    let body = strip_codes(body);
+    let (server, message) = body.strip_prefix('[')?.split_once(']')?;
+    let message = message.trim();
+    let (player, rest) = message.split_once(' ')?;
+    if !is_player_name(player) {
+        return None;
+    }
+    let kind = match rest {
+        "joined the game" => EventKind::Join,
+        "left the game" => EventKind::Leave,
+        _ if rest.starts_with("has made the advancement [")
+            || rest.starts_with("has completed the challenge [")
+            || rest.starts_with("has reached the goal [") =>
+        {
+            EventKind::Advancement
+        }
+        _ if DEATH_PHRASES.iter().any(|phrase| rest.starts_with(phrase)) => EventKind::Death,
+        _ => return None,
+    };
+    Some(ServerEvent {
+        server: Some(server.to_string()),
+        kind,
+        player: player.to_string(),
+        message: message.to_string(),
+    })
+}
+
+fn parse_list(body: &str) -> Result<Vec<ServerPlayers>, ProtocolError> {
+    body.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let (server, players) = line.split_once(':').ok_or(ProtocolError::Malformed {
+                command: "LIST",
+                reason: format!("expected `server:players`, got `{}`", line),
+            })?;
+            let players = players
+                .split(',')
+                .map(str::trim)
+                .filter(|p| !p.is_empty())
+                .map(str::to_string)
+                .collect();
+            Ok(ServerPlayers {
+                server: server.trim().to_string(),
+                players,
+            })
+        })
+        .collect()
+}
+
+fn looks_like_size(token: &str) -> bool {
+    token.starts_with(|c: char| c.is_ascii_digit())
+        && (token.chars().all(|c| c.is_ascii_digit() || c == '.')
+            || token.to_ascii_uppercase().ends_with('B'))
+}
+
+/// Parses a backup listing, either a JSON array of entries or names or one
+/// backup per line as `name [date...] [size]`
+fn parse_backups(body: &str) -> Vec<BackupEntry> {
+    if let Ok(entries) = serde_json::from_str::<Vec<BackupEntry>>(body) {
+        return entries;
+    }
+    if let Ok(names) = serde_json::from_str::<Vec<String>>(body) {
+        return names
+            .into_iter()
+            .map(|name| BackupEntry {
+                name,
+                date: None,
+                size: None,
+            })
+            .collect();
+    }
+    body.lines()
+        .filter_map(|line| {
+            let mut tokens = line.split_whitespace().collect::<Vec<_>>();
+            if tokens.is_empty() {
+                return None;
+            }
+            let name = tokens.remove(0).to_string();
+            let size = match tokens.last() {
+                Some(last) if looks_like_size(last) => tokens.pop().map(str::to_string),
+                _ => None,
+            };
+            let date = (!tokens.is_empty()).then(|| tokens.join(" "));
+            Some(BackupEntry { name, date, size })
+        })
+        .collect()
+}
+
+/// Parses one text frame received from Taurus
+pub fn parse(frame: &str) -> Result<Inbound, ProtocolError> {
+    let frame = frame.trim_end_matches(['\r', '\n']);
+    let (command, body) = frame.split_once(' ').unwrap_or((frame, ""));
+    match command {
+        "" => Err(ProtocolError::Empty),
+        "MSG" => Ok(match parse_chat(body) {
+            Some(chat) => Inbound::Chat(chat),
+            None => match parse_event(body) {
+                Some(event) => Inbound::Event(event),
+                None => Inbound::Msg(body.to_string()),
+            },
+        }),
+        "EVENT" => {
+            serde_json::from_str(body)
+                .map(Inbound::Event)
+                .map_err(|e| ProtocolError::Malformed {
+                    command: "EVENT",
+                    reason: e.to_string(),
+                })
+        }
+        "CONSOLE" => Ok(Inbound::Console(body.to_string())),
+        "RCON" => Ok(Inbound::Rcon(body.to_string())),
+        "LIST" => Ok(Inbound::List(parse_list(body)?)),
+        "LIST_SESSIONS" => serde_json::from_str(body)
+            .map(Inbound::ListSessions)
+            .map_err(|e| ProtocolError::Malformed {
+                command: "LIST_SESSIONS",
+                reason: e.to_string(),
+            }),
+        "BACKUP" => Ok(Inbound::Backup(body.to_string())),
+        "LIST_BACKUPS" => Ok(Inbound::ListBackups(parse_backups(body))),
+        "RM_BACKUP" => Ok(Inbound::RmBackup(body.to_string())),
+        "START_SESSION" => Ok(Inbound::StartSession(body.to_string())),
+        "STOP_SESSION" => Ok(Inbound::StopSession(body.to_string())),
+        "RESTART_SESSION" => Ok(Inbound::RestartSession(body.to_string())),
+        "SESSION_STATUS" => serde_json::from_str(body)
+            .map(Inbound::SessionStatus)
+            .map_err(|e| ProtocolError::Malformed {
+                command: "SESSION_STATUS",
+                reason: e.to_string(),
+            }),
+        "PING" => Ok(Inbound::Ping),
+        "PONG" => Ok(Inbound::Pong),
+        other => Err(ProtocolError::UnknownCommand(other.to_string())),
+    }
+}
+
+/// Checks a server, session or backup name, which Taurus reads up to the
+/// first space
+fn word<'a>(command: &'static str, word: &'a str) -> Result<&'a str, ProtocolError> {
+    if word.is_empty() || word.contains(char::is_whitespace) {
+        return Err(ProtocolError::Malformed {
+            command,
+            reason: format!("`{}` is not a single word", word),
+        });
+    }
+    Ok(word)
+}
+
+/// Serialises a frame to the text sent over the websocket
+pub fn encode(frame: &Outbound) -> Result<String, ProtocolError> {
+    let kind = frame.command();
+    Ok(match frame {
+        Outbound::Auth(password) => password.clone(),
+        Outbound::Msg(text) => format!("MSG {}", text),
+        Outbound::Rcon { server, command } => format!("RCON {} {}", word(kind, server)?, command),
+        Outbound::Url { url, label } => format!("URL {} {}", url, label),
+        Outbound::List => "LIST".to_string(),
+        Outbound::ListSessions => "LIST_SESSIONS".to_string(),
+        Outbound::Backup(name) => format!("BACKUP {}", word(kind, name)?),
+        Outbound::ListBackups => "LIST_BACKUPS".to_string(),
+        Outbound::RmBackup(name) => format!("RM_BACKUP {}", word(kind, name)?),
+        Outbound::StartSession(name) => format!("START_SESSION {}", word(kind, name)?),
+        Outbound::StopSession(name) => format!("STOP_SESSION {}", word(kind, name)?),
+        Outbound::RestartSession(name) => format!("RESTART_SESSION {}", word(kind, name)?),
+        Outbound::SessionStatus(name) => format!("SESSION_STATUS {}", word(kind, name)?),
+        Outbound::Ping => "PING".to_string(),
+        Outbound::Pong => "PONG".to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_empty_and_unknown_frames() {
+        assert!(matches!(parse(""), Err(ProtocolError::Empty)));
+        assert!(matches!(parse("\r\n"), Err(ProtocolError::Empty)));
+        assert!(matches!(
+            parse("NOPE body"),
+            Err(ProtocolError::UnknownCommand(command)) if command == "NOPE"
+        ));
+    }
+
+    #[test]
+    fn reports_malformed_bodies() {
+        for frame in [
+            "LIST SMP Steve",
+            "LIST_SESSIONS not json",
+            "SESSION_STATUS {}",
+            "EVENT {\"kind\":\"explode\"}",
+        ] {
+            assert!(
+                matches!(parse(frame), Err(ProtocolError::Malformed { .. })),
+                "{} should be malformed",
+                frame
+            );
+        }
+    }
+
+    #[test]
+    fn parses_empty_list() {
+        let Ok(Inbound::List(servers)) = parse("LIST") else {
+            panic!("expected LIST");
+        };
+        assert!(servers.is_empty());
+        let Ok(Inbound::List(servers)) = parse("LIST SMP:\nCMP: , ") else {
+            panic!("expected LIST");
+        };
+        assert_eq!(servers.len(), 2);
+        assert!(servers.iter().all(|s| s.players.is_empty()));
+    }
+
+    #[test]
+    fn parses_list_with_players() {
+        let Ok(Inbound::List(servers)) = parse("LIST SMP: Steve, Alex\n") else {
+            panic!("expected LIST");
+        };
+        assert_eq!(servers[0].server, "SMP");
+        assert_eq!(servers[0].players, ["Steve", "Alex"]);
+    }
+
+    #[test]
+    fn parses_chat() {
+        let Ok(Inbound::Chat(chat)) = parse("MSG [SMP] <Steve> hello there") else {
+            panic!("expected chat");
+        };
+        assert_eq!(chat.server, "SMP");
+        assert_eq!(chat.username, "Steve");
+        assert_eq!(chat.text, "hello there");
+    }
+
+    #[test]
+    fn recognises_events() {
+        let cases = [
+            ("MSG [SMP] Steve joined the game", EventKind::Join),
+            ("MSG [SMP] Steve left the game", EventKind::Leave),
+            ("MSG [SMP] Steve fell from a high place", EventKind::Death),
+            ("MSG §e[SMP] Steve was slain by Zombie", EventKind::Death),
+            (
+                "MSG [CMP] Alex has made the advancement [Stone Age]",
+                EventKind::Advancement,
+            ),
+        ];
+        for (frame, kind) in cases {
+            let Ok(Inbound::Event(event)) = parse(frame) else {
+                panic!("{} should be an event", frame);
+            };
+            assert_eq!(event.kind, kind, "{}", frame);
+        }
+        let Ok(Inbound::Event(event)) = parse("MSG [SMP] Steve joined the game") else {
+            panic!("expected event");
+        };
+        assert_eq!(event.server.as_deref(), Some("SMP"));
+        assert_eq!(event.player, "Steve");
+    }
+
+    #[test]
+    fn keeps_other_messages() {
+        for frame in [
+            "MSG Server restarting in 5 minutes",
+            "MSG [SMP] a joined the game",
+            "MSG Steve is here",
+            "MSG Steve joined the game",
+            "MSG Server was restarted",
+            "MSG Steve was here",
+            "MSG [SMP] Steve was here",
+            "MSG [SMP] Steve fell asleep",
+            "MSG",
+        ] {
+            assert!(
+                matches!(parse(frame), Ok(Inbound::Msg(_))),
+                "{} should stay a message",
+                frame
+            );
+        }
+    }
+
+    #[test]
+    fn parses_json_events() {
+        let frame =
+            r#"EVENT {"server":"SMP","kind":"death","player":"Steve","message":"Steve drowned"}"#;
+        let Ok(Inbound::Event(event)) = parse(frame) else {
+            panic!("expected event");
+        };
+        assert_eq!(event.kind, EventKind::Death);
+        assert_eq!(event.message, "Steve drowned");
+    }
+
+    #[test]
+    fn parses_json_backup_entries() {
+        let backups =
+            parse_backups(r#"[{"name":"smp-1","date":"2025-01-01","size":"2G"},{"name":"smp-2"}]"#);
+        assert_eq!(backups.len(), 2);
+        assert_eq!(backups[0].date.as_deref(), Some("2025-01-01"));
+        assert_eq!(backups[0].size.as_deref(), Some("2G"));
+        assert!(backups[1].date.is_none() && backups[1].size.is_none());
+    }
+
+    #[test]
+    fn parses_json_backup_names() {
+        let backups = parse_backups(r#"["smp-1", "smp-2"]"#);
+        let names = backups.iter().map(|b| b.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, ["smp-1", "smp-2"]);
+    }
+
+    #[test]
+    fn parses_line_backups() {
+        let backups =
+            parse_backups("smp-1 2025-01-01 04:00 1.5GB\nsmp-2 12345\n\nsmp-3 yesterday\n");
+        assert_eq!(backups.len(), 3);
+        assert_eq!(backups[0].name, "smp-1");
+        assert_eq!(backups[0].date.as_deref(), Some("2025-01-01 04:00"));
+        assert_eq!(backups[0].size.as_deref(), Some("1.5GB"));
+        assert_eq!(backups[1].size.as_deref(), Some("12345"));
+        assert!(backups[1].date.is_none());
+        assert_eq!(backups[2].date.as_deref(), Some("yesterday"));
+        assert!(backups[2].size.is_none());
+        assert!(parse_backups("").is_empty());
+    }
+
+    #[test]
+    fn encodes_frames() {
+        let rcon = Outbound::Rcon {
+            server: "SMP".to_string(),
+            command: "list".to_string(),
+        };
+        assert_eq!(encode(&rcon).unwrap(), "RCON SMP list");
+        assert_eq!(
+            encode(&Outbound::Auth("secret".to_string())).unwrap(),
+            "secret"
+        );
+        assert_eq!(encode(&Outbound::ListBackups).unwrap(), "LIST_BACKUPS");
+        assert_eq!(rcon.response(), Some("RCON"));
+        assert_eq!(Outbound::Msg(String::new()).response(), None);
+    }
+
+    #[test]
+    fn rejects_names_with_spaces() {
+        let rcon = Outbound::Rcon {
+            server: "SMP op".to_string(),
+            command: "Steve".to_string(),
+        };
+        assert!(matches!(
+            encode(&rcon),
+            Err(ProtocolError::Malformed { .. })
+        ));
+        assert!(encode(&Outbound::Backup(String::new())).is_err());
+        assert!(encode(&Outbound::StopSession("a b".to_string())).is_err());
+    }
+
+    #[test]
+    fn checks_player_names() {
+        assert!(is_player_name("Steve_123"));
+        assert!(!is_player_name("ab"));
+        assert!(!is_player_name("@a"));
+        assert!(!is_player_name("Steve Alex"));
+        assert!(!is_player_name("abcdefghijklmnopq"));
+    }
+}
@@ -0,0 +1,86 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use poise::serenity_prelude::{ChannelId, MessageId};
+
+use super::protocol::Outbound;
+
+/// A Discord message waiting to be bridged to Minecraft
+pub struct BridgeMessage {
+    pub frames: Vec<Outbound>,
+    /// The Discord message the frames were built from
+    pub origin: Option<(ChannelId, MessageId)>,
+    queued_at: Instant,
+}
+
+impl BridgeMessage {
+    pub fn new(frames: Vec<Outbound>, origin: Option<(ChannelId, MessageId)>) -> Self {
+        Self {
+            frames,
+            origin,
+            queued_at: Instant::now(),
+        }
+    }
+}
+
+/// Bounded buffer for bridged messages while Taurus is unreachable
+pub struct BridgeQueue {
+    messages: VecDeque<BridgeMessage>,
+    capacity: usize,
+    max_age: Duration,
+}
+
+impl BridgeQueue {
+    pub fn new(capacity: usize, max_age: Duration) -> Self {
+        Self {
+            messages: VecDeque::new(),
+            capacity,
+            max_age,
+        }
+    }
+
+    /// Queues a message, returning whatever had to be evicted to make room
+    pub fn push(&mut self, message: BridgeMessage) -> Vec<BridgeMessage> {
+        let mut dropped = self.expire();
+        if self.capacity == 0 {
+            dropped.push(message);
+            return dropped;
+        }
+        while self.messages.len() >= self.capacity {
+            dropped.extend(self.messages.pop_front());
+        }
+        self.messages.push_back(message);
+        dropped
+    }
+
+    /// Puts a message back at the front after a failed flush
+    pub fn requeue(&mut self, message: BridgeMessage) {
+        self.messages.push_front(message);
+    }
+
+    /// Removes and returns every message older than the age limit
+    pub fn expire(&mut self) -> Vec<BridgeMessage> {
+        let mut dropped = Vec::new();
+        while let Some(message) = self.messages.front() {
+            if message.queued_at.elapsed() <= self.max_age {
+                break;
+            }
+            dropped.extend(self.messages.pop_front());
+        }
+        dropped
+    }
+
+    pub fn pop(&mut self) -> Option<BridgeMessage> {
+        self.messages.pop_front()
+    }
+
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+}
@@ -0,0 +1,263 @@
+use std::{collections::HashSet, time::Duration};
+
+use poise::serenity_prelude::{
+    ChannelId, Context, CreateEmbed, CreateMessage, Error as SerenityError, GuildId, HttpError,
+    RoleId, UserId,
+};
+use tokio::time::sleep;
+
+use crate::{config::Config, links::Links, scoreboard::Scoreboards, taurus::taurus_client};
+
+/// Longest list of players shown in one embed field
+const FIELD_LIMIT: usize = 1000;
+/// Discord's error code for a user who is not in the guild
+const UNKNOWN_MEMBER: isize = 10007;
+
+/// What a sync run changes, or would change
+pub struct SyncPlan {
+    /// Linked players with the member role who are not whitelisted
+    pub add: Vec<(UserId, String)>,
+    /// Whitelisted players whose owner lost the member role or left
+    pub remove: Vec<(UserId, String)>,
+    /// Whitelisted players nobody has linked
+    pub unlinked: Vec<String>,
+}
+
+impl SyncPlan {
+    pub fn is_empty(&self) -> bool {
+        self.add.is_empty() && self.remove.is_empty()
+    }
+}
+
+/// Whether `user` is in the guild with the role, or `None` if Discord could
+/// not tell us
+pub async fn has_role(ctx: &Context, guild: GuildId, user: UserId, role: RoleId) -> Option<bool> {
+    match guild.member(ctx, user).await {
+        Ok(member) => Some(member.roles.contains(&role)),
+        Err(SerenityError::Http(HttpError::UnsuccessfulRequest(response)))
+            if response.error.code == UNKNOWN_MEMBER =>
+        {
+            Some(false)
+        }
+        Err(e) => {
+            println!("ERROR: Failed to fetch member {}: {}", user, e);
+            None
+        }
+    }
+}
+
+/// Compares the linked accounts against the member role and the whitelist
+pub async fn plan(ctx: &Context) -> Result<SyncPlan, String> {
+    let (guild, role, links) = {
+        let data = ctx.data.read().await;
+        let config = data.get::<Config>().expect("Config not found");
+        let opts = config
+            .whitelist_sync
+            .as_ref()
+            .ok_or("Whitelist sync is not configured")?;
+        let links = data
+            .get::<Links>()
+            .expect("Links not found")
+            .iter()
+            .map(|(user, player)| (user, player.to_string()))
+            .collect::<Vec<_>>();
+        (
+            GuildId::new(opts.guild),
+            RoleId::new(config.member_role),
+            links,
+        )
+    };
+    let whitelist = {
+        let mut data = ctx.data.write().await;
+        let scoreboards = data
+            .get_mut::<Scoreboards>()
+            .expect("Scoreboards not found");
+        scoreboards.load_whitelist()?;
+        scoreboards
+            .get_whitelist()
+            .iter()
+            .cloned()
+            .collect::<Vec<_>>()
+    };
+
+    let mut plan = SyncPlan {
+        add: Vec::new(),
+        remove: Vec::new(),
+        unlinked: Vec::new(),
+    };
+    for (user, player) in &links {
+        let whitelisted = whitelist
+            .iter()
+            .any(|listed| listed.eq_ignore_ascii_case(player));
+        match has_role(ctx, guild, *user, role).await {
+            Some(true) if !whitelisted => plan.add.push((*user, player.clone())),
+            Some(false) if whitelisted => plan.remove.push((*user, player.clone())),
+            _ => {}
+        }
+    }
+    plan.unlinked = whitelist
+        .into_iter()
+        .filter(|player| {
+            !links
+                .iter()
+                .any(|(_, linked)| linked.eq_ignore_ascii_case(player))
+        })
+        .collect();
+    plan.unlinked.sort();
+    Ok(plan)
+}
+
+/// Runs the plan over RCON on every server, removing players only if
+/// `remove` is set. Returns a line per failed command.
+pub async fn apply(ctx: &Context, plan: &SyncPlan, remove: bool) -> Vec<String> {
+    let servers = {
+        let data = ctx.data.read().await;
+        let config = data.get::<Config>().expect("Config not found");
+        config
+            .worlds
+            .iter()
+            .map(|world| world.name.clone())
+            .collect::<Vec<_>>()
+    };
+    let taurus = taurus_client(ctx).await;
+    let removals = if remove { plan.remove.as_slice() } else { &[] };
+    let commands = plan
+        .add
+        .iter()
+        .map(|(_, player)| format!("whitelist add {}", player))
+        .chain(
+            removals
+                .iter()
+                .map(|(_, player)| format!("whitelist remove {}", player)),
+        );
+
+    let mut failures = Vec::new();
+    for command in commands {
+        for server in &servers {
+            if let Err(e) = taurus.rcon(server, &command).await {
+                failures.push(format!("{} on {}: {}", command, server, e));
+            }
+        }
+    }
+
+    let mut data = ctx.data.write().await;
+    let scoreboards = data
+        .get_mut::<Scoreboards>()
+        .expect("Scoreboards not found");
+    if let Err(e) = scoreboards.load_whitelist() {
+        println!("ERROR: Failed to reload whitelist: {}", e);
+    }
+    failures
+}
+
+fn player_list(players: &[(UserId, String)]) -> String {
+    let names = players
+        .iter()
+        .map(|(user, player)| format!("`{}` (<@{}>)", player, user))
+        .collect::<Vec<_>>();
+    truncated_list(&names)
+}
+
+/// Joins names with commas, cutting the list off to fit in an embed field
+pub fn truncated_list(names: &[String]) -> String {
+    if names.is_empty() {
+        return "None".to_string();
+    }
+    let mut out = String::new();
+    for (i, name) in names.iter().enumerate() {
+        if out.len() + name.len() + 2 > FIELD_LIMIT {
+            out.push_str(&format!("… and {} more", names.len() - i));
+            break;
+        }
+        if !out.is_empty() {
+            out.push_str(", ");
+        }
+        out.push_str(name);
+    }
+    out
+}
+
+/// Adds the plan, and whether it was carried out, to an embed
+pub fn report(embed: CreateEmbed, plan: &SyncPlan, applied: bool, remove: bool) -> CreateEmbed {
+    let (add_title, remove_title) = match (applied, remove) {
+        (true, true) => ("Whitelisted", "Removed"),
+        (true, false) => ("Whitelisted", "Flagged, removal is disabled"),
+        (false, true) => ("Would whitelist", "Would remove"),
+        (false, false) => ("Would whitelist", "Would flag, removal is disabled"),
+    };
+    let unlinked = plan
+        .unlinked
+        .iter()
+        .map(|player| format!("`{}`", player))
+        .collect::<Vec<_>>();
+    embed
+        .field(add_title, player_list(&plan.add), false)
+        .field(remove_title, player_list(&plan.remove), false)
+        .field(
+            "Whitelisted but not linked",
+            truncated_list(&unlinked),
+            false,
+        )
+}
+
+/// Periodically syncs the whitelist, reporting changes to the staff channel
+pub async fn run_whitelist_sync(ctx: &Context) {
+    let Some((interval, remove, staff_channel)) = ({
+        let data = ctx.data.read().await;
+        let config = data.get::<Config>().expect("Config not found");
+        config.whitelist_sync.as_ref().map(|opts| {
+            (
+                Duration::from_secs(opts.interval.max(60)),
+                opts.remove,
+                config.staff_channel.map(ChannelId::new),
+            )
+        })
+    }) else {
+        return;
+    };
+    // Players already reported but left on the whitelist, so each is only
+    // reported once
+    let mut flagged = HashSet::new();
+    loop {
+        let plan = plan(ctx).await.map(|mut plan| {
+            if !remove {
+                let current = plan
+                    .remove
+                    .iter()
+                    .map(|(_, player)| player.clone())
+                    .collect::<HashSet<_>>();
+                plan.remove.retain(|(_, player)| !flagged.contains(player));
+                flagged = current;
+            }
+            plan
+        });
+        match plan {
+            Ok(plan) if plan.is_empty() => {}
+            Ok(plan) => {
+                let failures = apply(ctx, &plan, remove).await;
+                println!(
+                    "INFO: Whitelist sync added {} and flagged {} players",
+                    plan.add.len(),
+                    plan.remove.len()
+                );
+                let mut embed = report(
+                    CreateEmbed::new().title("Whitelist sync"),
+                    &plan,
+                    true,
+                    remove,
+                );
+                if !failures.is_empty() {
+                    embed = embed.field("Failed", truncated_list(&failures), false);
+                }
+                if let Some(channel) = staff_channel {
+                    let message = CreateMessage::new().embed(embed);
+                    if let Err(e) = channel.send_message(&ctx.http, message).await {
+                        println!("ERROR: Failed to report whitelist sync: {}", e);
+                    }
+                }
+            }
+            Err(e) => println!("ERROR: Whitelist sync failed: {}", e),
+        }
+        sleep(interval).await;
+    }
+}